@@ -0,0 +1,137 @@
+use std::path::PathBuf;
+
+use clap::{Arg, Command};
+
+#[derive(Debug, Default)]
+pub struct Args {
+    pub font: Option<PathBuf>,
+    pub masters: Vec<PathBuf>,
+    pub filter: Option<String>,
+    pub block: Option<String>,
+    pub mfekmetadata: bool,
+    // set if the `check` subcommand was given, in which case no window is opened
+    pub check: Option<CheckArgs>,
+    // set if the `generate` subcommand was given; no window is opened either
+    pub generate: Option<GenerateArgs>,
+}
+
+#[derive(Debug, Default)]
+pub struct CheckArgs {
+    pub masters: Vec<PathBuf>,
+    pub json: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct GenerateArgs {
+    pub designspace: PathBuf,
+    // names of the instances to generate; all of them if empty
+    pub instances: Vec<String>,
+}
+
+pub fn parse_args() -> Args {
+    let matches = Command::new("MFEKufo")
+        .version(env!("CARGO_PKG_VERSION"))
+        .author("Matthew Blanchard, Fredrick R. Brennan, & MFEK Authors")
+        .about("UFO editor for the MFEK project")
+        .args_conflicts_with_subcommands(true)
+        .subcommand(
+            Command::new("check")
+                .about("Check masters for interpolation compatibility without opening a window. Exits non-zero if they are incompatible.")
+                .arg(
+                    Arg::new("MASTERS")
+                        .help("The masters to check against each other")
+                        .required(true)
+                        .multiple_values(true)
+                        .min_values(2),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print the report as JSON"),
+                ),
+        )
+        .subcommand(
+            Command::new("generate")
+                .about("Interpolate the instances of a designspace and write them out as UFOs")
+                .arg(
+                    Arg::new("DESIGNSPACE")
+                        .help("The .designspace whose instances to generate")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("instance")
+                        .long("instance")
+                        .short('i')
+                        .takes_value(true)
+                        .multiple_occurrences(true)
+                        .value_name("NAME")
+                        .help("Only generate the instance with this name; may be given more than once"),
+                ),
+        )
+        .arg(
+            Arg::new("FONT")
+                .help("The UFO (.ufo directory or .ufoz archive) or .designspace to open")
+                .index(1),
+        )
+        .arg(
+            Arg::new("master")
+                .long("master")
+                .short('m')
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .value_name("UFO")
+                .requires("FONT")
+                .help("Open an additional master alongside FONT; may be given more than once"),
+        )
+        .arg(
+            Arg::new("filter")
+                .long("filter")
+                .short('f')
+                .takes_value(true)
+                .value_name("QUERY")
+                .help("Initial glyph name search string"),
+        )
+        .arg(
+            Arg::new("block")
+                .long("block")
+                .short('b')
+                .takes_value(true)
+                .value_name("NAME")
+                .help("Only show glyphs in this Unicode block, e.g. \"Basic Latin\""),
+        )
+        .arg(
+            Arg::new("mfekmetadata")
+                .long("mfekmetadata")
+                .global(true)
+                .help("Read glyph lists and fontinfo through an installed mfekmetadata instead of in-process"),
+        )
+        .get_matches();
+
+    Args {
+        font: matches.value_of("FONT").map(PathBuf::from),
+        masters: matches
+            .values_of("master")
+            .map(|v| v.map(PathBuf::from).collect())
+            .unwrap_or_default(),
+        filter: matches.value_of("filter").map(str::to_owned),
+        block: matches.value_of("block").map(str::to_owned),
+        mfekmetadata: matches.is_present("mfekmetadata"),
+        check: matches.subcommand_matches("check").map(|check| CheckArgs {
+            masters: check
+                .values_of("MASTERS")
+                .map(|v| v.map(PathBuf::from).collect())
+                .unwrap_or_default(),
+            json: check.is_present("json"),
+        }),
+        generate: matches.subcommand_matches("generate").map(|generate| GenerateArgs {
+            designspace: generate
+                .value_of("DESIGNSPACE")
+                .map(PathBuf::from)
+                .unwrap_or_default(),
+            instances: generate
+                .values_of("instance")
+                .map(|v| v.map(str::to_owned).collect())
+                .unwrap_or_default(),
+        }),
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+
+use colored::Colorize;
+use serde_json::json;
+
+use crate::{
+    args::CheckArgs,
+    interpolation::{self, GlyphReport, InterpolationCheckResults},
+    viewer::{UFOViewer, UFO},
+};
+
+// Exit codes of `MFEKufo check`.
+pub const EXIT_COMPATIBLE: i32 = 0;
+pub const EXIT_INCOMPATIBLE: i32 = 1;
+pub const EXIT_LOAD_FAILED: i32 = 2;
+
+/// Loads every master, checks them for interpolation compatibility and prints a report. Returns
+/// the process exit code.
+pub fn run(mut viewer: UFOViewer, args: CheckArgs) -> i32 {
+    let mut masters: Vec<UFO> = Vec::new();
+    for path in &args.masters {
+        match viewer.load_ufo_from_path(path) {
+            Ok(ufo) => {
+                for entry in ufo.broken_glyphs() {
+                    if let Some(error) = &entry.error {
+                        eprintln!("{} {}", "warning:".yellow().bold(), error);
+                    }
+                }
+                masters.push(ufo);
+            }
+            Err(e) => {
+                eprintln!("{} {}", "error:".red().bold(), e);
+                return EXIT_LOAD_FAILED;
+            }
+        }
+    }
+
+    let results = interpolation::check_interpolatable(&masters);
+
+    if args.json {
+        println!("{}", json_report(&masters, &results));
+    } else {
+        print_report(&masters, &results);
+    }
+
+    if results.succeeded {
+        EXIT_COMPATIBLE
+    } else {
+        EXIT_INCOMPATIBLE
+    }
+}
+
+fn sorted(set: &HashSet<String>) -> Vec<&String> {
+    let mut names: Vec<&String> = set.iter().collect();
+    names.sort();
+    names
+}
+
+fn json_report(masters: &[UFO], results: &InterpolationCheckResults) -> serde_json::Value {
+    json!({
+        "succeeded": results.succeeded,
+        "masters": masters
+            .iter()
+            .map(|m| m.source_path().to_string_lossy())
+            .collect::<Vec<_>>(),
+        "sparse_masters": masters
+            .iter()
+            .filter(|m| m.is_sparse())
+            .map(|m| m.source_path().to_string_lossy())
+            .collect::<Vec<_>>(),
+        "glyph_count": results.glyph_set.len(),
+        "missing_glyphs": sorted(&results.missing_glyphs),
+        "missing_by_master": masters
+            .iter()
+            .zip(&results.missing_by_master)
+            .map(|(m, missing)| (m.source_path().to_string_lossy().into_owned(), json!(sorted(missing))))
+            .collect::<serde_json::Map<_, _>>(),
+        "contour_count_collisions": sorted(&results.contour_count_collisions),
+        "point_count_collisions": sorted(&results.point_count_collisions),
+        "contour_open_collisions": sorted(&results.contour_open_collisions),
+        "point_type_collisions": sorted(&results.point_type_collisions),
+        "segment_collisions": sorted(&results.segment_collisions),
+        "component_collisions": sorted(&results.component_collisions),
+        "anchor_collisions": sorted(&results.anchor_collisions),
+        "contour_order_collisions": sorted(&results.contour_order_collisions),
+        "direction_collisions": sorted(&results.direction_collisions),
+        "start_point_collisions": sorted(&results.start_point_collisions),
+        "warnings": sorted(&results.warnings),
+        "glyphs": results
+            .reports
+            .iter()
+            .map(|(name, report)| (name.clone(), json_glyph_report(report)))
+            .collect::<serde_json::Map<_, _>>(),
+    })
+}
+
+// Per-master values are in the order the masters were given on the command line.
+fn json_glyph_report(report: &GlyphReport) -> serde_json::Value {
+    json!({
+        "glifname": report.glifname,
+        "missing_in": report.missing_in(),
+        "contour_counts": report.contour_counts,
+        "point_counts": report
+            .point_counts
+            .iter()
+            .map(|(ci, counts)| (ci.to_string(), json!(counts)))
+            .collect::<serde_json::Map<_, _>>(),
+        "open_states": report
+            .open_states
+            .iter()
+            .map(|(ci, states)| (ci.to_string(), json!(states)))
+            .collect::<serde_json::Map<_, _>>(),
+        "point_types": report
+            .point_types
+            .iter()
+            .map(|(ci, types)| (ci.to_string(), json!(types)))
+            .collect::<serde_json::Map<_, _>>(),
+        "segments": report
+            .segments
+            .iter()
+            .map(|(ci, segments)| (ci.to_string(), json!(segments)))
+            .collect::<serde_json::Map<_, _>>(),
+        "components": report.components,
+        "anchors": report.anchors,
+        "reference": report.reference,
+        "correspondences": report
+            .correspondences
+            .iter()
+            .map(|(mi, matches)| {
+                let matches: Vec<_> = matches
+                    .iter()
+                    .map(|m| json!({ "contour": m.contour, "reversed": m.reversed, "start": m.start }))
+                    .collect();
+                (mi.to_string(), json!(matches))
+            })
+            .collect::<serde_json::Map<_, _>>(),
+    })
+}
+
+fn print_report(masters: &[UFO], results: &InterpolationCheckResults) {
+    println!(
+        "Checked {} masters, {} glyphs:",
+        masters.len(),
+        results.glyph_set.len()
+    );
+    for master in masters {
+        if master.is_sparse() {
+            println!("  {} (sparse)", master.source_path().display());
+        } else {
+            println!("  {}", master.source_path().display());
+        }
+    }
+
+    if !results.missing_glyphs.is_empty() {
+        println!();
+        println!("{} ({}):", "Missing in some masters".bold(), results.missing_glyphs.len());
+        for name in sorted(&results.missing_glyphs) {
+            let missing_in: Vec<String> = masters
+                .iter()
+                .zip(&results.missing_by_master)
+                .filter(|(_, missing)| missing.contains(name))
+                .map(|(m, _)| m.source_path().display().to_string())
+                .collect();
+            println!("  {} (missing in {})", name, missing_in.join(", "));
+        }
+    }
+
+    let categories = [
+        ("Contour count mismatches", &results.contour_count_collisions),
+        ("Point count mismatches", &results.point_count_collisions),
+        ("Open/closed contour mismatches", &results.contour_open_collisions),
+        ("Point type mismatches", &results.point_type_collisions),
+        ("Segment structure mismatches", &results.segment_collisions),
+        ("Component mismatches", &results.component_collisions),
+        ("Anchor mismatches", &results.anchor_collisions),
+    ];
+    // guesses, which don't make the masters incompatible
+    let warnings = [
+        ("Contours possibly in a different order", &results.contour_order_collisions),
+        ("Contours possibly running the other way", &results.direction_collisions),
+        ("Contours possibly starting at a different point", &results.start_point_collisions),
+    ];
+
+    for (title, names) in categories {
+        if names.is_empty() {
+            continue;
+        }
+
+        println!();
+        println!("{} ({}):", title.bold(), names.len());
+        for name in sorted(names) {
+            println!("  {}", name);
+        }
+    }
+    for (title, names) in warnings {
+        if names.is_empty() {
+            continue;
+        }
+
+        println!();
+        println!("{} {} ({}):", "warning:".yellow().bold(), title.bold(), names.len());
+        for name in sorted(names) {
+            println!("  {}", name);
+        }
+    }
+
+    println!();
+    if results.succeeded {
+        println!("{}", "Masters are compatible.".green().bold());
+    } else {
+        println!("{}", "Masters are NOT compatible.".red().bold());
+    }
+}
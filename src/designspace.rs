@@ -0,0 +1,667 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::File,
+    path::{Component, Path, PathBuf},
+};
+
+use xmltree::{Element, EmitterConfig, XMLNode};
+
+use crate::error::LoadError;
+
+/// Coordinates along each axis, by axis name, in design space units.
+pub type Location = BTreeMap<String, f64>;
+
+/// A .designspace document: the axes of a family, the masters ("sources") at points along them
+/// and the named instances to generate.
+#[derive(Debug, Clone, Default)]
+pub struct Designspace {
+    // where the document was read from or last saved to
+    pub path: Option<PathBuf>,
+    pub axes: Vec<Axis>,
+    pub sources: Vec<Source>,
+    pub instances: Vec<Instance>,
+    // the document as read; `write` keeps whatever of it isn't modelled, like rules and <lib>
+    pub tree: Option<Element>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Axis {
+    pub name: String,
+    pub tag: String,
+    // in user space, as the designspace spec has it
+    pub minimum: f64,
+    pub default: f64,
+    pub maximum: f64,
+    // the only values a discrete axis (format 5) takes, between `minimum` and `maximum`; empty
+    // for a continuous axis
+    pub values: Vec<f64>,
+    // (user value, design value) pairs, sorted by user value
+    pub map: Vec<(f64, f64)>,
+    // the <axis> this was read from, e.g. for its labels
+    pub element: Option<Element>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Source {
+    pub name: Option<String>,
+    // the UFO, relative to the .designspace
+    pub filename: String,
+    pub familyname: Option<String>,
+    pub stylename: Option<String>,
+    // a layer of the UFO to use instead of the default one
+    pub layer: Option<String>,
+    pub location: Location,
+    // the <source> this was read from, e.g. for its <lib copy="1"/>
+    pub element: Option<Element>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Instance {
+    pub name: Option<String>,
+    pub familyname: Option<String>,
+    pub stylename: Option<String>,
+    pub postscriptfontname: Option<String>,
+    // where the instance UFO is generated to, relative to the .designspace
+    pub filename: Option<String>,
+    pub location: Location,
+    // the <instance> this was read from, e.g. for its <kerning/> and <glyphs>
+    pub element: Option<Element>,
+}
+
+pub fn is_designspace(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|ext| ext == "designspace")
+}
+
+impl Axis {
+    /// Maps a user space value to design space, linearly between the points of `map`.
+    pub fn to_design(&self, user: f64) -> f64 {
+        piecewise_linear(self.map.iter().copied(), user)
+    }
+
+    /// Maps a design space value back to user space.
+    pub fn to_user(&self, design: f64) -> f64 {
+        piecewise_linear(self.map.iter().map(|&(u, d)| (d, u)), design)
+    }
+
+    pub fn design_minimum(&self) -> f64 {
+        self.to_design(self.minimum)
+    }
+
+    pub fn design_default(&self) -> f64 {
+        self.to_design(self.default)
+    }
+
+    pub fn design_maximum(&self) -> f64 {
+        self.to_design(self.maximum)
+    }
+}
+
+// An empty mapping is the identity; values outside of it are clamped to its ends.
+fn piecewise_linear(points: impl Iterator<Item = (f64, f64)>, value: f64) -> f64 {
+    let mut points: Vec<(f64, f64)> = points.collect();
+    if points.is_empty() {
+        return value;
+    }
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let (first, last) = (points[0], points[points.len() - 1]);
+    if value <= first.0 {
+        return first.1;
+    }
+    if value >= last.0 {
+        return last.1;
+    }
+
+    for pair in points.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        if value <= x1 {
+            if x1 == x0 {
+                return y1;
+            }
+            return y0 + (value - x0) / (x1 - x0) * (y1 - y0);
+        }
+    }
+    last.1
+}
+
+impl Designspace {
+    pub fn load(path: &Path) -> Result<Self, LoadError> {
+        let error = |message: String| LoadError::Designspace {
+            path: path.to_owned(),
+            message,
+        };
+
+        let file = File::open(path).map_err(|e| error(e.to_string()))?;
+        let root = Element::parse(file).map_err(|e| error(e.to_string()))?;
+        if root.name != "designspace" {
+            return Err(error(format!("root element is <{}>, not <designspace>", root.name)));
+        }
+
+        let axes = children(&root, "axes", "axis")
+            .map(parse_axis)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(error)?;
+
+        let mut designspace = Designspace {
+            path: Some(path.to_owned()),
+            axes,
+            tree: Some(root.clone()),
+            ..Default::default()
+        };
+
+        designspace.sources = children(&root, "sources", "source")
+            .map(|e| designspace.parse_source(e))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(error)?;
+        designspace.instances = children(&root, "instances", "instance")
+            .map(|e| designspace.parse_instance(e))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(error)?;
+
+        Ok(designspace)
+    }
+
+    /// Writes the document to `path` as designspace format 4.1, or the format it was read as if
+    /// that is newer. Source and instance filenames are written as they are, so they should
+    /// already be relative to `path`'s directory.
+    ///
+    /// Whatever was read but isn't modelled, like rules, <lib>, format 5 labels and mappings, or
+    /// the <kerning/> and <glyphs> of an instance, is written back as it was.
+    pub fn write(&self, path: &Path) -> Result<(), LoadError> {
+        let error = |message: String| LoadError::Designspace {
+            path: path.to_owned(),
+            message,
+        };
+
+        let mut root = self.tree.clone().unwrap_or_else(|| Element::new("designspace"));
+        let format = match attr(&root, "format") {
+            Some(format) if format.parse::<f64>().is_ok_and(|f| f > 4.1) => format,
+            _ => "4.1".to_string(),
+        };
+        root.attributes.insert("format".to_string(), format);
+
+        let axes = self.axes.iter().map(|axis| {
+            // a discrete axis has no range, giving it one would make it continuous
+            let (range, values) = if axis.values.is_empty() {
+                ((Some(axis.minimum.to_string()), Some(axis.maximum.to_string())), None)
+            } else {
+                let values: Vec<String> = axis.values.iter().map(f64::to_string).collect();
+                ((None, None), Some(values.join(" ")))
+            };
+            let mut element = element_from(
+                axis.element.as_ref(),
+                "axis",
+                &[
+                    ("tag", Some(axis.tag.clone())),
+                    ("name", Some(axis.name.clone())),
+                    ("minimum", range.0),
+                    ("maximum", range.1),
+                    ("values", values),
+                    ("default", Some(axis.default.to_string())),
+                ],
+            );
+            let map = axis.map.iter().map(|(input, output)| {
+                element_with(
+                    "map",
+                    &[
+                        ("input", Some(input.to_string())),
+                        ("output", Some(output.to_string())),
+                    ],
+                )
+            });
+            replace_children(&mut element, "map", map.collect());
+            element
+        });
+        replace_group(&mut root, "axes", "axis", axes.collect());
+
+        let sources = self.sources.iter().map(|source| {
+            let mut element = element_from(
+                source.element.as_ref(),
+                "source",
+                &[
+                    ("filename", Some(source.filename.clone())),
+                    ("name", source.name.clone()),
+                    ("familyname", source.familyname.clone()),
+                    ("stylename", source.stylename.clone()),
+                    ("layer", source.layer.clone()),
+                ],
+            );
+            replace_children(&mut element, "location", vec![location_element(&source.location)]);
+            element
+        });
+        replace_group(&mut root, "sources", "source", sources.collect());
+
+        let instances = self.instances.iter().map(|instance| {
+            let mut element = element_from(
+                instance.element.as_ref(),
+                "instance",
+                &[
+                    ("name", instance.name.clone()),
+                    ("familyname", instance.familyname.clone()),
+                    ("stylename", instance.stylename.clone()),
+                    ("filename", instance.filename.clone()),
+                    ("postscriptfontname", instance.postscriptfontname.clone()),
+                ],
+            );
+            replace_children(&mut element, "location", vec![location_element(&instance.location)]);
+            element
+        });
+        replace_group(&mut root, "instances", "instance", instances.collect());
+
+        let file = File::create(path).map_err(|e| error(e.to_string()))?;
+        root.write_with_config(file, EmitterConfig::new().perform_indent(true))
+            .map_err(|e| error(e.to_string()))
+    }
+
+    /// Things that would make the document invalid, in the order they should be fixed.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut names = HashSet::new();
+        let mut tags = HashSet::new();
+
+        for axis in &self.axes {
+            if axis.name.is_empty() {
+                problems.push("Every axis needs a name".to_string());
+            } else if !names.insert(&axis.name) {
+                problems.push(format!("There is more than one axis named {}", axis.name));
+            }
+            if axis.tag.len() != 4 || !axis.tag.is_ascii() {
+                problems.push(format!("The tag of axis {} must be 4 ASCII characters", axis.name));
+            } else if !tags.insert(&axis.tag) {
+                problems.push(format!("There is more than one axis tagged {}", axis.tag));
+            }
+            if !(axis.minimum <= axis.default && axis.default <= axis.maximum) {
+                problems.push(format!(
+                    "The default of axis {} must lie between its minimum and maximum",
+                    axis.name
+                ));
+            } else if !axis.values.is_empty() && !axis.values.contains(&axis.default) {
+                problems.push(format!("The default of axis {} must be one of its values", axis.name));
+            }
+        }
+
+        if self.sources.is_empty() {
+            problems.push("There are no sources".to_string());
+        }
+
+        problems
+    }
+
+    pub fn axis(&self, name: &str) -> Option<&Axis> {
+        self.axes.iter().find(|axis| axis.name == name)
+    }
+
+    /// The directory source and instance filenames are relative to.
+    pub fn base_dir(&self) -> PathBuf {
+        self.path
+            .as_ref()
+            .and_then(|p| p.parent())
+            .map(Path::to_owned)
+            .unwrap_or_default()
+    }
+
+    pub fn source_path(&self, source: &Source) -> PathBuf {
+        self.base_dir().join(&source.filename)
+    }
+
+    /// `location` with every axis it leaves out set to that axis' default.
+    pub fn full_location(&self, location: &Location) -> Location {
+        self.axes
+            .iter()
+            .map(|axis| {
+                let value = location
+                    .get(&axis.name)
+                    .copied()
+                    .unwrap_or_else(|| axis.design_default());
+                (axis.name.clone(), value)
+            })
+            .collect()
+    }
+
+    fn parse_source(&self, element: &Element) -> Result<Source, String> {
+        Ok(Source {
+            name: attr(element, "name"),
+            filename: required(element, "filename")?,
+            familyname: attr(element, "familyname"),
+            stylename: attr(element, "stylename"),
+            layer: attr(element, "layer"),
+            location: self.parse_location(element)?,
+            element: Some(element.clone()),
+        })
+    }
+
+    fn parse_instance(&self, element: &Element) -> Result<Instance, String> {
+        Ok(Instance {
+            name: attr(element, "name"),
+            familyname: attr(element, "familyname"),
+            stylename: attr(element, "stylename"),
+            postscriptfontname: attr(element, "postscriptfontname"),
+            filename: attr(element, "filename"),
+            location: self.parse_location(element)?,
+            element: Some(element.clone()),
+        })
+    }
+
+    // Dimensions are in design space (`xvalue`); format 5 also allows user space (`uservalue`).
+    fn parse_location(&self, parent: &Element) -> Result<Location, String> {
+        let mut location = Location::new();
+
+        for dimension in children(parent, "location", "dimension") {
+            let name = required(dimension, "name")?;
+            let value = match (number(dimension, "xvalue")?, number(dimension, "uservalue")?) {
+                (Some(xvalue), _) => xvalue,
+                (None, Some(uservalue)) => self
+                    .axis(&name)
+                    .map_or(uservalue, |axis| axis.to_design(uservalue)),
+                (None, None) => {
+                    return Err(format!("<dimension name=\"{}\"> has no xvalue", name))
+                }
+            };
+            location.insert(name, value);
+        }
+
+        Ok(location)
+    }
+}
+
+fn parse_axis(element: &Element) -> Result<Axis, String> {
+    let name = required(element, "name")?;
+    let tag = required(element, "tag")?;
+
+    // discrete axes (format 5) list their values instead of giving a range
+    let values: Vec<f64> = match attr(element, "values") {
+        Some(values) => values
+            .split_whitespace()
+            .map(|v| v.parse().map_err(|_| format!("axis {}: bad value {:?}", name, v)))
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+    let bound = |key: &str, fallback: Option<f64>| -> Result<f64, String> {
+        number(element, key)?
+            .or(fallback)
+            .ok_or_else(|| format!("axis {} has no {}", name, key))
+    };
+    let minimum = bound("minimum", values.iter().copied().reduce(f64::min))?;
+    let maximum = bound("maximum", values.iter().copied().reduce(f64::max))?;
+    let default = bound("default", None)?;
+
+    let mut map = Vec::new();
+    for point in element.children.iter().filter_map(XMLNode::as_element) {
+        if point.name != "map" {
+            continue;
+        }
+        let input = number(point, "input")?.ok_or("<map> has no input")?;
+        let output = number(point, "output")?.ok_or("<map> has no output")?;
+        map.push((input, output));
+    }
+    map.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    Ok(Axis {
+        name,
+        tag,
+        minimum,
+        default,
+        maximum,
+        values,
+        map,
+        element: Some(element.clone()),
+    })
+}
+
+fn element_with(name: &str, attributes: &[(&str, Option<String>)]) -> Element {
+    let mut element = Element::new(name);
+    for (key, value) in attributes {
+        if let Some(value) = value {
+            element.attributes.insert(key.to_string(), value.clone());
+        }
+    }
+    element
+}
+
+// `original` with the given attributes set or, where `None`, removed. Attributes and children
+// that aren't given are kept.
+fn element_from(
+    original: Option<&Element>,
+    name: &str,
+    attributes: &[(&str, Option<String>)],
+) -> Element {
+    let mut element = original.cloned().unwrap_or_else(|| Element::new(name));
+    for (key, value) in attributes {
+        match value {
+            Some(value) => {
+                element.attributes.insert(key.to_string(), value.clone());
+            }
+            None => {
+                element.attributes.shift_remove(*key);
+            }
+        }
+    }
+    element
+}
+
+fn push_child(parent: &mut Element, child: Element) {
+    parent.children.push(XMLNode::Element(child));
+}
+
+// Replaces `parent`'s `name` children with `new`, placed where the first of the old ones was, or
+// else first. Other children are left where they are.
+fn replace_children(parent: &mut Element, name: &str, new: Vec<Element>) {
+    let is_replaced = |node: &XMLNode| node.as_element().is_some_and(|e| e.name == name);
+    let at = parent.children.iter().position(is_replaced).unwrap_or(0);
+    parent.children.retain(|node| !is_replaced(node));
+    let tail = parent.children.split_off(at.min(parent.children.len()));
+    parent.children.extend(new.into_iter().map(XMLNode::Element));
+    parent.children.extend(tail);
+}
+
+// Replaces the `name` elements of `root`'s `group` child, e.g. every <axis> in <axes>, keeping
+// the rest of the group. A group left empty is dropped.
+fn replace_group(root: &mut Element, group: &str, name: &str, new: Vec<Element>) {
+    let position = root
+        .children
+        .iter()
+        .position(|node| node.as_element().is_some_and(|e| e.name == group));
+    let mut element = match position {
+        Some(i) => match root.children.remove(i) {
+            XMLNode::Element(element) => element,
+            _ => unreachable!(),
+        },
+        None => Element::new(group),
+    };
+    replace_children(&mut element, name, new);
+
+    if !element.children.iter().any(|node| node.as_element().is_some()) {
+        return;
+    }
+    let at = position.unwrap_or(root.children.len());
+    root.children.insert(at, XMLNode::Element(element));
+}
+
+fn location_element(location: &Location) -> Element {
+    let mut element = Element::new("location");
+    for (name, value) in location {
+        push_child(
+            &mut element,
+            element_with(
+                "dimension",
+                &[("name", Some(name.clone())), ("xvalue", Some(value.to_string()))],
+            ),
+        );
+    }
+    element
+}
+
+/// `path` relative to the directory `base`, with `/` separators as the designspace spec wants.
+/// Falls back to the absolute path if the two don't share a root.
+pub fn relative_path(path: &Path, base: &Path) -> String {
+    let path_components: Vec<Component> = path.components().collect();
+    let base_components: Vec<Component> = base.components().collect();
+
+    let common = path_components
+        .iter()
+        .zip(&base_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return path.to_string_lossy().into_owned();
+    }
+
+    let mut parts: Vec<String> = vec!["..".to_string(); base_components.len() - common];
+    parts.extend(
+        path_components[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    parts.join("/")
+}
+
+/// `filename`, relative to the directory `from`, made relative to the directory `to` instead.
+pub fn rebase(filename: &str, from: &Path, to: &Path) -> String {
+    if from == to {
+        return filename.to_string();
+    }
+
+    // resolve `..` without touching the disk, the file needn't exist yet
+    let mut path = PathBuf::new();
+    for component in from.join(filename).components() {
+        let last = path.components().next_back();
+        match component {
+            Component::ParentDir if matches!(last, Some(Component::Normal(_))) => {
+                path.pop();
+            }
+            Component::CurDir => {}
+            component => path.push(component),
+        }
+    }
+    relative_path(&path, to)
+}
+
+// The `name` elements inside `parent`'s `group` child, e.g. every <axis> in <axes>.
+fn children<'a>(
+    parent: &'a Element,
+    group: &str,
+    name: &'a str,
+) -> impl Iterator<Item = &'a Element> {
+    parent
+        .get_child(group)
+        .into_iter()
+        .flat_map(|group| group.children.iter().filter_map(XMLNode::as_element))
+        .filter(move |element| element.name == name)
+}
+
+fn attr(element: &Element, key: &str) -> Option<String> {
+    element.attributes.get(key).cloned()
+}
+
+fn required(element: &Element, key: &str) -> Result<String, String> {
+    attr(element, key).ok_or_else(|| format!("<{}> has no {} attribute", element.name, key))
+}
+
+fn number(element: &Element, key: &str) -> Result<Option<f64>, String> {
+    attr(element, key)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("{} of <{}> is not a number: {:?}", key, element.name, value))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<designspace format="5.0">
+  <axes elidedfallbackname="Regular">
+    <axis tag="wght" name="Weight" minimum="100" maximum="900" default="400">
+      <labels>
+        <label uservalue="400" name="Regular" elidable="true"/>
+      </labels>
+    </axis>
+    <axis tag="ital" name="Italic" values="0 1" default="0"/>
+    <mappings>
+      <mapping>
+        <input><dimension name="Weight" xvalue="400"/></input>
+        <output><dimension name="Weight" xvalue="450"/></output>
+      </mapping>
+    </mappings>
+  </axes>
+  <rules>
+    <rule name="dollar">
+      <conditionset><condition name="Weight" minimum="600" maximum="900"/></conditionset>
+      <sub name="dollar" with="dollar.heavy"/>
+    </rule>
+  </rules>
+  <sources>
+    <source filename="Light.ufo" name="Light">
+      <lib copy="1"/>
+      <location><dimension name="Weight" xvalue="100"/></location>
+    </source>
+  </sources>
+  <instances>
+    <instance name="Bold" filename="instances/Bold.ufo">
+      <location><dimension name="Weight" xvalue="700"/></location>
+      <kerning/>
+      <info/>
+    </instance>
+  </instances>
+  <lib>
+    <dict><key>com.example.key</key><string>value</string></dict>
+  </lib>
+</designspace>
+"#;
+
+    #[test]
+    fn write_keeps_unmodelled_elements() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Test.designspace");
+        std::fs::write(&path, DOCUMENT).unwrap();
+
+        let mut designspace = Designspace::load(&path).unwrap();
+        designspace.axes[0].name = "Heaviness".to_string();
+        designspace.instances[0].location.insert("Heaviness".to_string(), 800.);
+        designspace.write(&path).unwrap();
+
+        let root = Element::parse(File::open(&path).unwrap()).unwrap();
+        assert_eq!(attr(&root, "format").as_deref(), Some("5.0"));
+        assert!(root.get_child("rules").is_some());
+        assert!(root.get_child("lib").is_some());
+
+        let axes = root.get_child("axes").unwrap();
+        assert_eq!(attr(axes, "elidedfallbackname").as_deref(), Some("Regular"));
+        assert!(axes.get_child("mappings").is_some());
+        let axis = axes.get_child("axis").unwrap();
+        assert_eq!(attr(axis, "name").as_deref(), Some("Heaviness"));
+        assert!(axis.get_child("labels").is_some());
+
+        let discrete = children(&root, "axes", "axis").nth(1).unwrap();
+        assert_eq!(attr(discrete, "values").as_deref(), Some("0 1"));
+        assert_eq!(attr(discrete, "default").as_deref(), Some("0"));
+        assert_eq!(attr(discrete, "minimum"), None);
+        assert_eq!(attr(discrete, "maximum"), None);
+        let reloaded = Designspace::load(&path).unwrap();
+        assert_eq!(reloaded.axes[1].values, vec![0., 1.]);
+        assert_eq!((reloaded.axes[1].minimum, reloaded.axes[1].maximum), (0., 1.));
+
+        let source = children(&root, "sources", "source").next().unwrap();
+        assert!(source.get_child("lib").is_some());
+
+        let instance = children(&root, "instances", "instance").next().unwrap();
+        assert!(instance.get_child("kerning").is_some());
+        assert!(instance.get_child("info").is_some());
+        let locations = instance
+            .children
+            .iter()
+            .filter(|node| node.as_element().is_some_and(|e| e.name == "location"));
+        assert_eq!(locations.count(), 1);
+    }
+
+    #[test]
+    fn rebase_filenames() {
+        let (from, to) = (Path::new("/fonts/src"), Path::new("/fonts/build"));
+        assert_eq!(rebase("instances/Bold.ufo", from, to), "../src/instances/Bold.ufo");
+        assert_eq!(rebase("../out/Bold.ufo", from, to), "../out/Bold.ufo");
+        assert_eq!(rebase("Bold.ufo", from, from), "Bold.ufo");
+    }
+}
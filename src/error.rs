@@ -0,0 +1,82 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Everything that can go wrong while opening a font. Errors are kept as strings so that they can
+/// be cloned into `GlyphEntry`s and shown to the user long after the original error is gone.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    /// The font as a whole couldn't be opened.
+    Font { path: PathBuf, message: String },
+    /// A .ufoz archive couldn't be unpacked or written back.
+    Archive { path: PathBuf, message: String },
+    /// A layer's contents.plist is missing or malformed.
+    Contents { path: PathBuf, message: String },
+    /// fontinfo.plist is malformed or lacks a key we can't do without.
+    FontInfo { path: PathBuf, message: String },
+    /// A .designspace document is malformed or names a source that can't be found.
+    Designspace { path: PathBuf, message: String },
+    /// An interpolated instance couldn't be written.
+    Instance { path: PathBuf, message: String },
+    /// A glyph couldn't be copied into another master.
+    Copy { path: PathBuf, message: String },
+    /// A setting kept in a UFO's lib.plist couldn't be saved.
+    Lib { path: PathBuf, message: String },
+    /// A single .glif failed to parse. Non-fatal: the glyph is flagged and the font still opens.
+    Glif { path: PathBuf, message: String },
+    /// mfekmetadata failed to run or printed something we don't understand.
+    Subprocess(String),
+}
+
+impl LoadError {
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            LoadError::Font { path, .. }
+            | LoadError::Archive { path, .. }
+            | LoadError::Contents { path, .. }
+            | LoadError::FontInfo { path, .. }
+            | LoadError::Designspace { path, .. }
+            | LoadError::Instance { path, .. }
+            | LoadError::Copy { path, .. }
+            | LoadError::Lib { path, .. }
+            | LoadError::Glif { path, .. } => Some(path),
+            LoadError::Subprocess(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Font { path, message } => {
+                write!(f, "Failed to open font {}: {}", path.display(), message)
+            }
+            LoadError::Archive { path, message } => {
+                write!(f, "Failed to read or write archive {}: {}", path.display(), message)
+            }
+            LoadError::Contents { path, message } => {
+                write!(f, "Failed to read {}: {}", path.display(), message)
+            }
+            LoadError::FontInfo { path, message } => {
+                write!(f, "Failed to read fontinfo {}: {}", path.display(), message)
+            }
+            LoadError::Designspace { path, message } => {
+                write!(f, "Failed to read designspace {}: {}", path.display(), message)
+            }
+            LoadError::Instance { path, message } => {
+                write!(f, "Failed to generate instance {}: {}", path.display(), message)
+            }
+            LoadError::Copy { path, message } => {
+                write!(f, "Failed to copy glyph to {}: {}", path.display(), message)
+            }
+            LoadError::Lib { path, message } => {
+                write!(f, "Failed to write {}: {}", path.display(), message)
+            }
+            LoadError::Glif { path, message } => {
+                write!(f, "Failed to load glyph {}: {}", path.display(), message)
+            }
+            LoadError::Subprocess(message) => write!(f, "mfekmetadata: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
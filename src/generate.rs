@@ -0,0 +1,92 @@
+use colored::Colorize;
+
+use crate::{
+    args::GenerateArgs,
+    designspace::{Designspace, Instance},
+    interpolation::instance::Interpolator,
+    viewer::{UFOViewer, UFO},
+};
+
+// Exit codes of `MFEKufo generate`.
+pub const EXIT_GENERATED: i32 = 0;
+pub const EXIT_GENERATE_FAILED: i32 = 1;
+pub const EXIT_LOAD_FAILED: i32 = 2;
+
+/// Loads every source of the designspace and writes out its instances. Returns the process exit
+/// code.
+pub fn run(mut viewer: UFOViewer, args: GenerateArgs) -> i32 {
+    let designspace = match Designspace::load(&args.designspace) {
+        Ok(designspace) => designspace,
+        Err(e) => {
+            eprintln!("{} {}", "error:".red().bold(), e);
+            return EXIT_LOAD_FAILED;
+        }
+    };
+
+    let mut masters: Vec<UFO> = Vec::new();
+    for source in &designspace.sources {
+        let loaded = viewer
+            .load_ufo_from_path(&designspace.source_path(source))
+            .and_then(|mut ufo| {
+                if let Some(layer) = &source.layer {
+                    ufo.use_layer(layer)?;
+                }
+                Ok(ufo)
+            });
+        match loaded {
+            Ok(mut ufo) => {
+                ufo.location = designspace.full_location(&source.location);
+                masters.push(ufo);
+            }
+            Err(e) => {
+                eprintln!("{} {}", "error:".red().bold(), e);
+                return EXIT_LOAD_FAILED;
+            }
+        }
+    }
+
+    let instances: Vec<&Instance> = designspace
+        .instances
+        .iter()
+        .filter(|i| {
+            args.instances.is_empty()
+                || i.name.as_ref().is_some_and(|name| args.instances.contains(name))
+        })
+        .collect();
+    if instances.is_empty() {
+        eprintln!("{} no instances to generate", "error:".red().bold());
+        return EXIT_GENERATE_FAILED;
+    }
+
+    let interpolator = match Interpolator::new(&designspace, &masters) {
+        Ok(interpolator) => interpolator,
+        Err(e) => {
+            eprintln!("{} can't interpolate: {}", "error:".red().bold(), e);
+            return EXIT_GENERATE_FAILED;
+        }
+    };
+
+    let mut code = EXIT_GENERATED;
+    for instance in instances {
+        match interpolator.generate(instance) {
+            Ok(report) => {
+                println!("Generated {} ({} glyphs)", report.path.display(), report.glyphs);
+                if !report.skipped.is_empty() {
+                    println!(
+                        "  {} {} glyphs couldn't be interpolated and were copied from the default master:",
+                        "warning:".yellow().bold(),
+                        report.skipped.len()
+                    );
+                    for name in &report.skipped {
+                        println!("    {}", name);
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("{} {}", "error:".red().bold(), e);
+                code = EXIT_GENERATE_FAILED;
+            }
+        }
+    }
+    code
+}
@@ -0,0 +1,415 @@
+use std::path::{Path, PathBuf};
+
+use egui::Color32;
+
+use super::filedialog;
+use crate::{
+    designspace::{self, Axis, Designspace, Instance, Location, Source},
+    interpolation::instance::Interpolator,
+    viewer::UFO,
+};
+
+/// State of the Designspace tab that isn't part of the document itself.
+#[derive(Debug, Default)]
+pub struct DesignspaceEditor {
+    status: Option<String>,
+    // the masters `Designspace::sources` was last built from, by path
+    synced_masters: Vec<PathBuf>,
+}
+
+pub fn designspace_ui(
+    ui: &mut egui::Ui,
+    designspace: &mut Designspace,
+    masters: &mut [UFO],
+    editor: &mut DesignspaceEditor,
+) {
+    // the masters are the sources; the document just records where they sit. Names and locations
+    // are picked up when saving.
+    let target = save_path(designspace, masters);
+    let synced = editor.synced_masters.iter().map(PathBuf::as_path);
+    if !masters.iter().map(UFO::source_path).eq(synced) {
+        let base_dir = target.parent().map(Path::to_owned).unwrap_or_default();
+        designspace.sources = sources_from_masters(designspace, masters, &base_dir);
+        editor.synced_masters = masters.iter().map(|m| m.source_path().to_owned()).collect();
+    }
+    let problems = designspace.problems();
+
+    ui.horizontal(|ui| {
+        let save = ui
+            .add_enabled(problems.is_empty(), egui::Button::new("Save"))
+            .on_hover_text(target.display().to_string())
+            .clicked();
+        let save_as = ui
+            .add_enabled(problems.is_empty(), egui::Button::new("Save As…"))
+            .clicked();
+
+        let path = match (save, save_as) {
+            (true, _) => Some(target.clone()),
+            (_, true) => filedialog::save_designspace(None),
+            _ => None,
+        };
+        if let Some(path) = path {
+            editor.status = Some(match save_designspace(designspace, masters, path) {
+                Ok(path) => format!("Saved {}", path.display()),
+                Err(e) => e,
+            });
+        }
+
+        let can_generate = can_generate(designspace, masters, &problems);
+        if ui
+            .add_enabled(can_generate.is_ok(), egui::Button::new("Generate All Instances"))
+            .on_disabled_hover_text(can_generate.err().unwrap_or_default())
+            .clicked()
+        {
+            editor.status = Some(generate(designspace, masters, &designspace.instances));
+        }
+
+        if let Some(status) = &editor.status {
+            ui.label(status);
+        }
+    });
+    for problem in &problems {
+        ui.colored_label(Color32::RED, problem);
+    }
+    ui.separator();
+
+    egui::ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            egui::CollapsingHeader::new("Axes")
+                .default_open(true)
+                .show(ui, |ui| axes_ui(ui, designspace, masters));
+            egui::CollapsingHeader::new("Masters")
+                .default_open(true)
+                .show(ui, |ui| masters_ui(ui, &designspace.axes, masters));
+            egui::CollapsingHeader::new("Instances")
+                .default_open(true)
+                .show(ui, |ui| instances_ui(ui, designspace, masters, &problems, editor));
+        });
+}
+
+fn axes_ui(ui: &mut egui::Ui, designspace: &mut Designspace, masters: &mut [UFO]) {
+    let mut removed = None;
+    let mut renamed = None;
+
+    for (i, axis) in designspace.axes.iter_mut().enumerate() {
+        egui::Grid::new(("axis", i)).num_columns(2).show(ui, |ui| {
+            ui.label("Name");
+            let old_name = axis.name.clone();
+            if ui.text_edit_singleline(&mut axis.name).changed() {
+                renamed = Some((old_name, axis.name.clone()));
+            }
+            ui.end_row();
+
+            ui.label("Tag");
+            ui.text_edit_singleline(&mut axis.tag);
+            ui.end_row();
+
+            // a discrete axis' values are kept as they were read
+            if axis.values.is_empty() {
+                ui.label("Minimum");
+                ui.add(egui::DragValue::new(&mut axis.minimum));
+                ui.end_row();
+            } else {
+                ui.label("Values");
+                let values: Vec<String> = axis.values.iter().map(f64::to_string).collect();
+                ui.label(values.join(" "));
+                ui.end_row();
+            }
+            ui.label("Default");
+            ui.add(egui::DragValue::new(&mut axis.default));
+            ui.end_row();
+            if axis.values.is_empty() {
+                ui.label("Maximum");
+                ui.add(egui::DragValue::new(&mut axis.maximum));
+                ui.end_row();
+            }
+        });
+
+        map_ui(ui, i, axis);
+
+        if ui.button("Remove axis").clicked() {
+            removed = Some(i);
+        }
+        ui.separator();
+    }
+
+    if ui.button("Add axis").clicked() {
+        designspace.axes.push(Axis {
+            name: format!("Axis {}", designspace.axes.len() + 1),
+            tag: "XXXX".to_string(),
+            minimum: 0.,
+            default: 0.,
+            maximum: 1000.,
+            values: Vec::new(),
+            map: Vec::new(),
+            element: None,
+        });
+    }
+
+    // locations are keyed by axis name, so they have to follow the axis around
+    let locations = masters
+        .iter_mut()
+        .map(|m| &mut m.location)
+        .chain(designspace.instances.iter_mut().map(|i| &mut i.location));
+    if let Some((old_name, new_name)) = renamed {
+        for location in locations {
+            if let Some(value) = location.remove(&old_name) {
+                location.insert(new_name.clone(), value);
+            }
+        }
+    } else if let Some(i) = removed {
+        let axis = designspace.axes.remove(i);
+        for location in locations {
+            location.remove(&axis.name);
+        }
+    }
+}
+
+// The user → design space mapping of an axis, one (input, output) pair per row.
+fn map_ui(ui: &mut egui::Ui, idx: usize, axis: &mut Axis) {
+    egui::CollapsingHeader::new("Mapping")
+        .id_source(("map", idx))
+        .show(ui, |ui| {
+            let mut removed = None;
+            egui::Grid::new(("map grid", idx)).num_columns(3).show(ui, |ui| {
+                ui.label("User");
+                ui.label("Design");
+                ui.end_row();
+
+                for (i, (input, output)) in axis.map.iter_mut().enumerate() {
+                    ui.add(egui::DragValue::new(input));
+                    ui.add(egui::DragValue::new(output));
+                    if ui.small_button("✖").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+
+            if let Some(i) = removed {
+                axis.map.remove(i);
+            }
+            if ui.button("Add point").clicked() {
+                axis.map.push((axis.default, axis.design_default()));
+            }
+        });
+}
+
+fn masters_ui(ui: &mut egui::Ui, axes: &[Axis], masters: &mut [UFO]) {
+    egui::Grid::new("master locations")
+        .num_columns(axes.len() + 1)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("Source name");
+            for axis in axes {
+                ui.label(&axis.name);
+            }
+            ui.end_row();
+
+            for master in masters.iter_mut() {
+                let mut name = master.display_name().to_owned();
+                if ui.text_edit_singleline(&mut name).changed() {
+                    master.source_name = Some(name);
+                }
+                location_ui(ui, axes, &mut master.location);
+                ui.end_row();
+            }
+        });
+}
+
+fn instances_ui(
+    ui: &mut egui::Ui,
+    designspace: &mut Designspace,
+    masters: &[UFO],
+    problems: &[String],
+    editor: &mut DesignspaceEditor,
+) {
+    let can_generate = can_generate(designspace, masters, problems);
+    let axes = &designspace.axes;
+    let mut removed = None;
+    let mut generated = None;
+
+    for (i, instance) in designspace.instances.iter_mut().enumerate() {
+        egui::Grid::new(("instance", i)).num_columns(2).show(ui, |ui| {
+            for (label, value) in [
+                ("Name", &mut instance.name),
+                ("Family name", &mut instance.familyname),
+                ("Style name", &mut instance.stylename),
+                ("PostScript name", &mut instance.postscriptfontname),
+                ("File", &mut instance.filename),
+            ] {
+                ui.label(label);
+                optional_text(ui, value);
+                ui.end_row();
+            }
+        });
+
+        ui.horizontal(|ui| location_ui(ui, axes, &mut instance.location));
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(can_generate.is_ok(), egui::Button::new("Generate"))
+                .on_disabled_hover_text(can_generate.clone().err().unwrap_or_default())
+                .clicked()
+            {
+                generated = Some(i);
+            }
+            if ui.button("Remove instance").clicked() {
+                removed = Some(i);
+            }
+        });
+        ui.separator();
+    }
+
+    if let Some(i) = generated {
+        let instance = designspace.instances[i].clone();
+        editor.status = Some(generate(designspace, masters, &[instance]));
+    }
+    if let Some(i) = removed {
+        designspace.instances.remove(i);
+    }
+    if ui.button("Add instance").clicked() {
+        let n = designspace.instances.len() + 1;
+        let location = designspace.full_location(&Location::new());
+        designspace.instances.push(Instance {
+            name: Some(format!("Instance {}", n)),
+            filename: Some(format!("instances/Instance{}.ufo", n)),
+            location,
+            ..Default::default()
+        });
+    }
+}
+
+// One slider per axis, in design space.
+fn location_ui(ui: &mut egui::Ui, axes: &[Axis], location: &mut Location) {
+    for axis in axes {
+        let value = location
+            .entry(axis.name.clone())
+            .or_insert_with(|| axis.design_default());
+        let range = axis.design_minimum()..=axis.design_maximum();
+        ui.add(egui::DragValue::new(value).clamp_range(range).prefix(format!("{} ", axis.tag)));
+    }
+}
+
+// An empty field unsets the attribute.
+fn optional_text(ui: &mut egui::Ui, value: &mut Option<String>) {
+    let mut text = value.clone().unwrap_or_default();
+    if ui.text_edit_singleline(&mut text).changed() {
+        *value = if text.is_empty() { None } else { Some(text) };
+    }
+}
+
+// Instances are written relative to the document, so it has to be saved somewhere first.
+fn can_generate(designspace: &Designspace, masters: &[UFO], problems: &[String]) -> Result<(), String> {
+    if !problems.is_empty() {
+        Err("Fix the problems listed above first".to_string())
+    } else if designspace.path.is_none() {
+        Err("Save the designspace first".to_string())
+    } else if masters.iter().any(UFO::is_loading) {
+        Err("Wait for every master to finish loading".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+fn generate(designspace: &Designspace, masters: &[UFO], instances: &[Instance]) -> String {
+    let interpolator = match Interpolator::new(designspace, masters) {
+        Ok(interpolator) => interpolator,
+        Err(e) => return format!("Can't interpolate: {}", e),
+    };
+
+    let lines: Vec<String> = instances
+        .iter()
+        .map(|instance| match interpolator.generate(instance) {
+            Ok(report) if report.skipped.is_empty() => {
+                format!("Generated {}", report.path.display())
+            }
+            Ok(report) => format!(
+                "Generated {}; copied {} incompatible glyphs from the default master: {}",
+                report.path.display(),
+                report.skipped.len(),
+                report.skipped.join(", ")
+            ),
+            Err(e) => e.to_string(),
+        })
+        .collect();
+    lines.join("\n")
+}
+
+// Where Save writes: where the document came from, or else next to the first master.
+fn save_path(designspace: &Designspace, masters: &[UFO]) -> PathBuf {
+    if let Some(path) = &designspace.path {
+        return path.clone();
+    }
+
+    let family = masters
+        .first()
+        .and_then(|m| m.metadata.info.family_name.clone())
+        .unwrap_or_else(|| "font".to_string());
+    let dir = masters
+        .first()
+        .and_then(|m| m.source_path().parent())
+        .map(Path::to_owned)
+        .unwrap_or_default();
+    dir.join(format!("{}.designspace", family))
+}
+
+// Attributes the editor doesn't show, like a source's family name, are kept from the source the
+// master was opened as. Sources are in the same order as the masters.
+fn sources_from_masters(designspace: &Designspace, masters: &[UFO], base_dir: &Path) -> Vec<Source> {
+    masters
+        .iter()
+        .map(|master| {
+            let filename = designspace::relative_path(master.source_path(), base_dir);
+            let previous = designspace
+                .sources
+                .iter()
+                .find(|s| designspace.source_path(s) == master.source_path());
+            let info = &master.metadata.info;
+
+            Source {
+                name: Some(master.display_name().to_owned()),
+                filename,
+                familyname: previous
+                    .and_then(|s| s.familyname.clone())
+                    .or_else(|| info.family_name.clone()),
+                stylename: previous
+                    .and_then(|s| s.stylename.clone())
+                    .or_else(|| info.style_name.clone()),
+                layer: master.source_layer.clone(),
+                location: designspace.full_location(&master.location),
+                element: previous.and_then(|s| s.element.clone()),
+            }
+        })
+        .collect()
+}
+
+fn save_designspace(
+    designspace: &mut Designspace,
+    masters: &[UFO],
+    mut path: PathBuf,
+) -> Result<PathBuf, String> {
+    if path.extension().is_none() {
+        path.set_extension("designspace");
+    }
+
+    // filenames are relative to the document, which may have moved
+    let base_dir = path.parent().map(Path::to_owned).unwrap_or_default();
+    designspace.sources = sources_from_masters(designspace, masters, &base_dir);
+    if designspace.path.is_some() {
+        let old_dir = designspace.base_dir();
+        for instance in &mut designspace.instances {
+            if let Some(filename) = &mut instance.filename {
+                *filename = designspace::rebase(filename, &old_dir, &base_dir);
+            }
+        }
+    }
+    for axis in &mut designspace.axes {
+        axis.map.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+
+    designspace.write(&path).map_err(|e| e.to_string())?;
+    designspace.path = Some(path.clone());
+    Ok(path)
+}
@@ -6,3 +6,24 @@ pub fn open_folder(start_in: Option<&str>) -> Option<PathBuf> {
         Ok(_) | Err(_) => None,
     }
 }
+
+pub fn open_archive(start_in: Option<&str>) -> Option<PathBuf> {
+    match nfd::open_file_dialog(Some("ufoz,zip"), start_in) {
+        Ok(nfd::Response::Okay(file)) => Some(file.into()),
+        Ok(_) | Err(_) => None,
+    }
+}
+
+pub fn save_designspace(start_in: Option<&str>) -> Option<PathBuf> {
+    match nfd::open_save_dialog(Some("designspace"), start_in) {
+        Ok(nfd::Response::Okay(file)) => Some(file.into()),
+        Ok(_) | Err(_) => None,
+    }
+}
+
+pub fn open_designspace(start_in: Option<&str>) -> Option<PathBuf> {
+    match nfd::open_file_dialog(Some("designspace"), start_in) {
+        Ok(nfd::Response::Okay(file)) => Some(file.into()),
+        Ok(_) | Err(_) => None,
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use egui::Color32;
+
+use crate::{
+    parsing::fontinfo::{FieldGroup, FontInfo},
+    viewer::UFO,
+};
+
+/// Edit buffers of one master's Font Info tab. Text is kept separately from `FontInfo` so that
+/// half-typed or invalid values don't get lost while the user is still typing.
+#[derive(Debug, Default)]
+pub struct FontInfoEditor {
+    text: HashMap<&'static str, String>,
+    // text that doesn't parse as the key's type, by key
+    parse_errors: HashMap<&'static str, String>,
+    // values that parse but break the spec's constraints, by key
+    problems: HashMap<&'static str, String>,
+    status: Option<String>,
+    loaded: bool,
+}
+
+impl FontInfoEditor {
+    fn load(&mut self, info: &FontInfo) {
+        self.text = FontInfo::FIELDS
+            .iter()
+            .map(|(_, key)| (*key, info.get_text(key).unwrap_or_default()))
+            .collect();
+        self.parse_errors.clear();
+        self.validate(info);
+        self.loaded = true;
+    }
+
+    fn validate(&mut self, info: &FontInfo) {
+        self.problems = info.validate().into_iter().collect();
+    }
+
+    fn error(&self, key: &str) -> Option<&String> {
+        self.parse_errors.get(key).or_else(|| self.problems.get(key))
+    }
+
+    fn can_save(&self) -> bool {
+        self.parse_errors.is_empty() && self.problems.is_empty()
+    }
+}
+
+pub fn fontinfo_ui(ui: &mut egui::Ui, ufo: &mut UFO, editor: &mut FontInfoEditor) {
+    if !editor.loaded {
+        editor.load(&ufo.metadata.info);
+    }
+
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(editor.can_save(), egui::Button::new("Save"))
+            .on_disabled_hover_text("Fix the fields marked in red first")
+            .clicked()
+        {
+            editor.status = Some(match ufo.metadata.write_fontinfo(&ufo.path) {
+                Ok(()) => "Saved fontinfo.plist".to_string(),
+                Err(e) => e.to_string(),
+            });
+        }
+
+        // back to what fontinfo.plist held when it was last read or written
+        if ui.button("Revert").clicked() {
+            ufo.metadata.info = FontInfo::from_dict(&ufo.metadata.raw);
+            editor.load(&ufo.metadata.info);
+            editor.status = None;
+        }
+
+        if let Some(status) = &editor.status {
+            ui.label(status);
+        }
+    });
+    ui.separator();
+
+    egui::ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            for group in [
+                FieldGroup::Naming,
+                FieldGroup::Dimensions,
+                FieldGroup::OpenType,
+                FieldGroup::PostScript,
+                FieldGroup::Macintosh,
+                FieldGroup::Woff,
+            ] {
+                egui::CollapsingHeader::new(group.label())
+                    .default_open(group == FieldGroup::Naming || group == FieldGroup::Dimensions)
+                    .show(ui, |ui| {
+                        egui::Grid::new(group.label())
+                            .num_columns(3)
+                            .striped(true)
+                            .show(ui, |ui| group_ui(ui, group, ufo, editor));
+                    });
+            }
+        });
+}
+
+fn group_ui(ui: &mut egui::Ui, group: FieldGroup, ufo: &mut UFO, editor: &mut FontInfoEditor) {
+    let mut changed = false;
+
+    for &(_, key) in FontInfo::FIELDS.iter().filter(|(g, _)| *g == group) {
+        ui.label(key);
+
+        let text = editor.text.entry(key).or_default();
+        if ui.text_edit_singleline(text).changed() {
+            match ufo.metadata.info.set_text(key, text) {
+                Ok(()) => {
+                    editor.parse_errors.remove(key);
+                }
+                Err(e) => {
+                    editor.parse_errors.insert(key, e);
+                }
+            }
+            changed = true;
+        }
+
+        match editor.error(key) {
+            Some(error) => ui.colored_label(Color32::RED, error),
+            None => ui.label(""),
+        };
+        ui.end_row();
+    }
+
+    if changed {
+        editor.validate(&ufo.metadata.info);
+        editor.status = None;
+    }
+}
@@ -1,15 +1,28 @@
 use egui_dock::{DockArea, Style};
 
-use crate::{ufo_cache::UFOCache, viewer::UFOViewer, gui::mastertab::MasterTabViewer};
+use crate::{
+    gui::{mastertab::MasterTabViewer, report::ReportAction},
+    ufo_cache::UFOCache,
+    viewer::UFOViewer,
+};
 
 pub fn fontview(ctx: &egui::Context, viewer: &mut UFOViewer, ufo_cache: &mut UFOCache) {
     ufo_cache.create_default_texture(ctx);
 
     if let Some(ufo) = viewer.get_active_master() {
-        ufo_cache.rebuild_images(ctx, &ufo.metadata, &viewer.interpolation_check);
+        let interpolate = viewer
+            .instance_preview
+            .interpolator(viewer.designspace.as_ref(), &viewer.masters);
+        ufo_cache.rebuild_images(
+            ctx,
+            &ufo.metadata,
+            &viewer.interpolation_check,
+            interpolate.as_deref(),
+        );
     }
 
     ufo_cache.clear_rebuild();
+    viewer.handle_load_events();
     viewer.handle_filesystem_events();
 
     filter_side_panel(ctx, viewer);
@@ -17,6 +30,21 @@ pub fn fontview(ctx: &egui::Context, viewer: &mut UFOViewer, ufo_cache: &mut UFO
     let original_style = ctx.style().clone();
 
         if viewer.get_active_master().is_some() {
+            let mut tab_viewer = MasterTabViewer {
+                masters: &mut viewer.masters,
+                ufo_cache,
+                fontinfo_editors: &mut viewer.fontinfo_editors,
+                designspace: &mut viewer.designspace,
+                designspace_editor: &mut viewer.designspace_editor,
+                instance_preview: &mut viewer.instance_preview,
+                interpolation_check: viewer.interpolation_check.as_ref(),
+                report_view: &mut viewer.report_view,
+                filter_string: viewer.filter_string.clone(),
+                filter_block: viewer.filter_block.clone(),
+                filter_request: None,
+                report_action: None,
+            };
+
             DockArea::new(&mut viewer.dockstate)
                 .show_close_buttons(false)
                 .draggable_tabs(true)
@@ -26,15 +54,17 @@ pub fn fontview(ctx: &egui::Context, viewer: &mut UFOViewer, ufo_cache: &mut UFO
                     style.tab_bar.fill_tab_bar = true;
                     style
                 })
-                .show(
-                    ctx,
-                    &mut MasterTabViewer {
-                        masters: &mut viewer.masters,
-                        ufo_cache,
-                        filter_string: viewer.filter_string.clone(),
-                        filter_block: viewer.filter_block.clone(),
-                    }
-                );
+                .show(ctx, &mut tab_viewer);
+
+            // the report asked to narrow the grid down to one glyph
+            if let Some(glifname) = tab_viewer.filter_request.take() {
+                viewer.filter_string = glifname;
+                viewer.filter_block = None;
+            }
+
+            if let Some(action) = tab_viewer.report_action.take() {
+                report_action(viewer, action);
+            }
         } else {
             egui::CentralPanel::default().show(ctx, |ui| {
                 ui.vertical_centered(|ui| {
@@ -48,6 +78,39 @@ pub fn fontview(ctx: &egui::Context, viewer: &mut UFOViewer, ufo_cache: &mut UFO
     ctx.set_style(original_style);
 }
 
+// Carries out what the report asked for, and tells it how that went.
+fn report_action(viewer: &mut UFOViewer, action: ReportAction) {
+    let status = match action {
+        ReportAction::MatchContours {
+            glifname,
+            reference,
+            targets,
+        } => match viewer.match_contours(&glifname, reference, &targets) {
+            Ok(0) => format!("{} needed no fixing", glifname),
+            Ok(1) => format!("Rewrote {} in 1 master", glifname),
+            Ok(n) => format!("Rewrote {} in {} masters", glifname, n),
+            Err(e) => e.to_string(),
+        },
+        ReportAction::SyncGlyphSets { reference, scale } => {
+            match viewer.synchronize_glyph_sets(reference, scale) {
+                Ok(n) => format!("Copied {} glyphs; they're listed for review above", n),
+                Err(e) => e.to_string(),
+            }
+        }
+        ReportAction::MarkReviewed { master, glifname } => {
+            match viewer.mark_reviewed(master, &glifname) {
+                Ok(()) => return,
+                Err(e) => e.to_string(),
+            }
+        }
+        ReportAction::SetSparse { master, sparse } => match viewer.set_sparse(master, sparse) {
+            Ok(()) => return,
+            Err(e) => e.to_string(),
+        },
+    };
+    viewer.report_view.status = Some(status);
+}
+
 fn filter_side_panel(ctx: &egui::Context, viewer: &mut UFOViewer) {
     let mut filter_block = viewer.filter_block.to_owned();
 
@@ -0,0 +1,219 @@
+use std::rc::Rc;
+
+use egui::Color32;
+use glifparser::Glif;
+
+use super::mastertab::{set_grid_style, visible_glyphs};
+use crate::{
+    designspace::{Axis, Designspace, Location},
+    interpolation::instance::{interpolate_glyph, Interpolator, MasterModel},
+    ufo_cache::UFOCache,
+    viewer::UFO,
+};
+
+// A designspace's model, with the axes and master locations it was built for.
+#[derive(Debug)]
+struct CachedModel {
+    axes: Vec<Axis>,
+    locations: Vec<Location>,
+    model: Result<Rc<MasterModel>, String>,
+}
+
+impl CachedModel {
+    fn is_for(&self, designspace: &Designspace, masters: &[UFO]) -> bool {
+        self.axes == designspace.axes
+            && self.locations.iter().eq(masters.iter().map(|m| &m.location))
+    }
+}
+
+/// Where the Instance tab interpolates the masters.
+#[derive(Debug)]
+pub struct InstancePreview {
+    // with a designspace, by axis name
+    location: Location,
+    // without one, `t` of the way from master `from` to master `to`
+    from: usize,
+    to: usize,
+    t: f64,
+    // the master weights the thumbnails were last drawn with, the master the glyph grid follows,
+    // and a counter bumped whenever they change so that `UFOCache` redraws them
+    weights: Vec<f64>,
+    base: usize,
+    generation: u64,
+    // only rebuilt when the axes or the masters' locations change
+    model: Option<CachedModel>,
+}
+
+impl Default for InstancePreview {
+    fn default() -> Self {
+        InstancePreview {
+            location: Location::new(),
+            from: 0,
+            to: 1,
+            t: 0.5,
+            weights: Vec::new(),
+            base: 0,
+            generation: 0,
+            model: None,
+        }
+    }
+}
+
+impl InstancePreview {
+    fn update_model(&mut self, designspace: Option<&Designspace>, masters: &[UFO]) {
+        let designspace = match designspace {
+            Some(designspace) => designspace,
+            None => {
+                self.model = None;
+                return;
+            }
+        };
+        if self.model.as_ref().is_some_and(|m| m.is_for(designspace, masters)) {
+            return;
+        }
+        self.model = Some(CachedModel {
+            axes: designspace.axes.clone(),
+            locations: masters.iter().map(|m| m.location.clone()).collect(),
+            model: MasterModel::new(designspace, masters).map(Rc::new),
+        });
+    }
+
+    /// Interpolates glyphs at the preview's current location, for `UFOCache` to draw. None if the
+    /// masters have changed since the Instance tab was last shown.
+    pub fn interpolator<'a>(
+        &'a self,
+        designspace: Option<&'a Designspace>,
+        masters: &'a [UFO],
+    ) -> Option<Box<dyn Fn(&str) -> Option<Glif<()>> + 'a>> {
+        if self.weights.len() != masters.len() || masters.iter().any(UFO::is_loading) {
+            return None;
+        }
+
+        match (designspace.filter(|d| !d.axes.is_empty()), &self.model) {
+            (Some(designspace), Some(cached)) if cached.is_for(designspace, masters) => {
+                if let Ok(model) = &cached.model {
+                    // the designspace's model leaves out sparse masters lacking the glyph
+                    let interpolator =
+                        Interpolator::with_model(designspace, masters, model.clone());
+                    return Some(Box::new(move |glifname: &str| {
+                        interpolator.glyph(glifname, &self.location)
+                    }));
+                }
+            }
+            (Some(_), _) => return None,
+            (None, _) => {}
+        }
+        Some(Box::new(move |glifname: &str| {
+            interpolate_glyph(masters, glifname, &self.weights, self.base)
+        }))
+    }
+}
+
+pub fn instance_ui(
+    ui: &mut egui::Ui,
+    designspace: Option<&Designspace>,
+    masters: &[UFO],
+    preview: &mut InstancePreview,
+    ufo_cache: &mut UFOCache,
+    filter_string: &str,
+    filter_block: &Option<String>,
+) {
+    if masters.len() < 2 {
+        ui.label("Open at least two masters to preview instances.");
+        return;
+    }
+    if masters.iter().any(UFO::is_loading) {
+        ui.label("Waiting for every master to finish loading…");
+        return;
+    }
+
+    let designspace = designspace.filter(|d| !d.axes.is_empty());
+    preview.update_model(designspace, masters);
+    let interpolator = match (designspace, preview.model.as_ref().map(|m| &m.model)) {
+        (Some(designspace), Some(Ok(model))) => {
+            let interpolator = Interpolator::with_model(designspace, masters, model.clone());
+            axis_sliders(ui, designspace, &mut preview.location);
+            Some(interpolator)
+        }
+        (_, Some(Err(e))) => {
+            ui.colored_label(Color32::RED, format!("Can't interpolate the designspace: {}", e));
+            None
+        }
+        _ => None,
+    };
+    let (weights, base) = match &interpolator {
+        Some(interpolator) => (
+            interpolator.weights(&preview.location),
+            interpolator.default_index(),
+        ),
+        None => two_master_slider(ui, masters, preview),
+    };
+
+    if weights != preview.weights || base != preview.base {
+        preview.weights = weights;
+        preview.base = base;
+        preview.generation += 1;
+    }
+    let generation = preview.generation;
+    let ufo = &masters[base];
+
+    egui::ScrollArea::vertical()
+        .stick_to_right(true)
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            set_grid_style(ui);
+
+            ui.set_width(ui.available_width());
+            ui.set_max_width(ui.available_width());
+            ui.horizontal_wrapped(|ui| {
+                for entry in visible_glyphs(ufo, filter_string, filter_block) {
+                    let glyph_image = ufo_cache.get_instance_image_handle(entry, generation);
+                    ui.add(egui::ImageButton::new(glyph_image, [128., 128.]));
+                }
+            });
+        });
+}
+
+fn axis_sliders(ui: &mut egui::Ui, designspace: &Designspace, location: &mut Location) {
+    for axis in &designspace.axes {
+        let value = location
+            .entry(axis.name.clone())
+            .or_insert_with(|| axis.design_default());
+        ui.add(
+            egui::Slider::new(value, axis.design_minimum()..=axis.design_maximum())
+                .text(&axis.name),
+        );
+    }
+}
+
+// Without a designspace, blends any two of the masters. Returns the weights and the master the
+// glyph grid follows.
+fn two_master_slider(
+    ui: &mut egui::Ui,
+    masters: &[UFO],
+    preview: &mut InstancePreview,
+) -> (Vec<f64>, usize) {
+    preview.from = preview.from.min(masters.len() - 1);
+    preview.to = preview.to.min(masters.len() - 1);
+
+    ui.horizontal(|ui| {
+        master_combo(ui, "from", masters, &mut preview.from);
+        ui.add(egui::Slider::new(&mut preview.t, 0.0..=1.0));
+        master_combo(ui, "to", masters, &mut preview.to);
+    });
+
+    let mut weights = vec![0.; masters.len()];
+    weights[preview.from] += 1. - preview.t;
+    weights[preview.to] += preview.t;
+    (weights, preview.from)
+}
+
+fn master_combo(ui: &mut egui::Ui, id: &str, masters: &[UFO], selected: &mut usize) {
+    egui::ComboBox::from_id_source(("instance master", id))
+        .selected_text(masters[*selected].display_name())
+        .show_ui(ui, |ui| {
+            for (i, master) in masters.iter().enumerate() {
+                ui.selectable_value(selected, i, master.display_name());
+            }
+        });
+}
@@ -1,87 +1,132 @@
-use std::{collections::HashSet, process::Command};
+use std::{
+    collections::{HashMap, HashSet},
+    process::Command,
+};
 
 use egui::{style::WidgetVisuals, Color32, Stroke, Style};
 use egui_dock::TabViewer;
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 
-use crate::{parsing::glyph_entries::GlyphEntry, ufo_cache::UFOCache, viewer::UFO};
+use super::{
+    designspace::{self, DesignspaceEditor},
+    fontinfo::{self, FontInfoEditor},
+    instance::{self, InstancePreview},
+    report::{self, ReportAction, ReportView},
+};
+use crate::{
+    designspace::Designspace,
+    interpolation::InterpolationCheckResults,
+    parsing::{glyph_entries::GlyphEntry, layer::DEFAULT_LAYER_NAME},
+    ufo_cache::UFOCache,
+    viewer::{Tab, UFO},
+};
 
 pub struct MasterTabViewer <'a> {
     pub masters: &'a mut Vec<UFO>,
     pub ufo_cache: &'a mut UFOCache,
+    pub fontinfo_editors: &'a mut HashMap<usize, FontInfoEditor>,
+    pub designspace: &'a mut Option<Designspace>,
+    pub designspace_editor: &'a mut DesignspaceEditor,
+    pub instance_preview: &'a mut InstancePreview,
+    pub interpolation_check: Option<&'a InterpolationCheckResults>,
+    pub report_view: &'a mut ReportView,
     pub filter_string: String,
     pub filter_block: Option<String>,
+    // set by the report to replace the search string
+    pub filter_request: Option<String>,
+    // set by the report to change the masters on disk
+    pub report_action: Option<ReportAction>,
 }
 
 impl<'a> TabViewer for MasterTabViewer<'a> {
-    type Tab = usize;
+    type Tab = Tab;
 
     fn title(&mut self, tab: &mut Self::Tab) -> egui::WidgetText {
-        self.masters[*tab].metadata.name.clone().into()
+        match *tab {
+            Tab::Master(idx) => self.masters[idx].display_name().into(),
+            Tab::FontInfo(idx) => format!("Font Info — {}", self.masters[idx].display_name()).into(),
+            Tab::Designspace => "Designspace".into(),
+            Tab::Instance => "Instance".into(),
+            Tab::Report => "Interpolation Report".into(),
+        }
     }
 
     fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
-        println!("UID {tab}");
+        match *tab {
+            Tab::Master(idx) => self.master_ui(ui, idx),
+            Tab::FontInfo(idx) => {
+                let editor = self.fontinfo_editors.entry(idx).or_default();
+                fontinfo::fontinfo_ui(ui, &mut self.masters[idx], editor);
+            }
+            Tab::Designspace => {
+                if let Some(doc) = self.designspace {
+                    designspace::designspace_ui(ui, doc, self.masters, self.designspace_editor);
+                }
+            }
+            Tab::Instance => instance::instance_ui(
+                ui,
+                self.designspace.as_ref(),
+                self.masters,
+                self.instance_preview,
+                self.ufo_cache,
+                &self.filter_string,
+                &self.filter_block,
+            ),
+            Tab::Report => report::report_ui(
+                ui,
+                self.interpolation_check,
+                self.masters,
+                self.ufo_cache,
+                self.report_view,
+                &mut self.filter_request,
+                &mut self.report_action,
+            ),
+        }
+    }
+
+    fn allowed_in_windows(&self, _tab: &mut Self::Tab) -> bool {
+        false
+    }
+
+    fn closeable(&mut self, tab: &mut Self::Tab) -> bool {
+        !matches!(tab, Tab::Master(_))
+    }
+}
+
+impl<'a> MasterTabViewer<'a> {
+    fn master_ui(&mut self, ui: &mut egui::Ui, idx: usize) {
         let filter_string = &self.filter_string;
         let filter_block = &self.filter_block;
-        let ufo = &self.masters[*tab];
+
+        if !self.masters[idx].layers.is_empty() {
+            layer_selectors(ui, idx, &mut self.masters[idx]);
+        }
+
+        let ufo = &self.masters[idx];
+
+        // the grid stays usable while glyphs stream in
+        if let Some(progress) = ufo.loading {
+            ui.add(
+                egui::ProgressBar::new(progress.fraction())
+                    .text(format!("Loading glyphs… {}/{}", progress.loaded, progress.total))
+                    .animate(true),
+            );
+        }
+
         egui::ScrollArea::vertical()
             .stick_to_right(true)
             .auto_shrink([false, false])
             .show(ui, |ui| {
-                let frame = WidgetVisuals {
-                    bg_fill: Color32::from_white_alpha(0),
-                    weak_bg_fill: Color32::from_white_alpha(0),
-                    bg_stroke: Stroke::new(8., Color32::from_white_alpha(0)),
-                    rounding: egui::Rounding::default(),
-                    fg_stroke: Stroke::new(8., Color32::from_white_alpha(0)),
-                    expansion: 0.,
-                };
-                
-                ui.set_style(Style {
-                    visuals: egui::Visuals {
-                        widgets: egui::style::Widgets {
-                            active: frame, // Set the custom frame style for ImageButtons
-                            inactive: frame,
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                });
+                set_grid_style(ui);
 
                 ui.set_width(ui.available_width());
                 ui.set_max_width(ui.available_width());
                 ui.horizontal_wrapped(|ui| {
-                    let filtered_vec: Vec<&GlyphEntry> =
-                        filter_glyphs(&ufo.glyph_entries, &filter_string.to_lowercase());
-
-                    let filtered_set: HashSet<_> = filtered_vec.into_iter().cloned().collect();
-
-                    let visible_set: HashSet<_> = if let Some(block_name) = &filter_block
-                    {
-                        ufo.unicode_blocks
-                            .iter()
-                            .find(|block| block.name == *block_name)
-                            .map(|block| {
-                                let block_set: HashSet<String> =
-                                    block.glyphs.iter().map(|x| x.name.clone()).collect();
-                                filtered_set
-                                    .iter()
-                                    .filter(|x| block_set.contains(&x.glifname))
-                                    .cloned()
-                                    .collect()
-                            })
-                            .unwrap_or_else(HashSet::new)
-                    } else {
-                        filtered_set
-                    };
-
-                    ufo.glyph_entries
-                        .iter()
-                        .filter(|entry| visible_set.contains(entry))
+                    visible_glyphs(ufo, filter_string, filter_block)
+                        .into_iter()
                         .for_each(|entry| {
-                            let glyph_image = self.ufo_cache.get_image_handle(&entry);
+                            let background = ufo.background_entry(&entry.glifname);
+                            let glyph_image = self.ufo_cache.get_image_handle(&entry, background);
 
                             let response =
                                 ui.add(egui::ImageButton::new(glyph_image, [128., 128.]));
@@ -98,16 +143,96 @@ impl<'a> TabViewer for MasterTabViewer<'a> {
                 });
             });
     }
+}
 
-    fn allowed_in_windows(&self, _tab: &mut Self::Tab) -> bool {
-        false
-    }
+/// The foreground layer shown in the grid, and the layer drawn faded out behind it.
+fn layer_selectors(ui: &mut egui::Ui, idx: usize, ufo: &mut UFO) {
+    ui.horizontal(|ui| {
+        ui.label("Layer");
+        egui::ComboBox::from_id_source(("layer", idx))
+            .selected_text(ufo.layer_name(ufo.active_layer).to_owned())
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut ufo.active_layer, None, DEFAULT_LAYER_NAME);
+                for (i, layer) in ufo.layers.iter().enumerate() {
+                    ui.selectable_value(&mut ufo.active_layer, Some(i), &layer.name);
+                }
+            });
 
-    fn closeable(&mut self, _tab: &mut Self::Tab) -> bool {
-        false
-    }
+        ui.label("Background");
+        let background_name = match ufo.background_layer {
+            Some(_) => ufo.layer_name(ufo.background_layer).to_owned(),
+            None => "None".to_owned(),
+        };
+        egui::ComboBox::from_id_source(("background", idx))
+            .selected_text(background_name)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut ufo.background_layer, None, "None");
+                for (i, layer) in ufo.layers.iter().enumerate() {
+                    ui.selectable_value(&mut ufo.background_layer, Some(i), &layer.name);
+                }
+            });
+    });
+}
+
+/// Makes the ImageButtons of a glyph grid frameless.
+pub(super) fn set_grid_style(ui: &mut egui::Ui) {
+    let frame = WidgetVisuals {
+        bg_fill: Color32::from_white_alpha(0),
+        weak_bg_fill: Color32::from_white_alpha(0),
+        bg_stroke: Stroke::new(8., Color32::from_white_alpha(0)),
+        rounding: egui::Rounding::default(),
+        fg_stroke: Stroke::new(8., Color32::from_white_alpha(0)),
+        expansion: 0.,
+    };
+
+    ui.set_style(Style {
+        visuals: egui::Visuals {
+            widgets: egui::style::Widgets {
+                active: frame, // Set the custom frame style for ImageButtons
+                inactive: frame,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    });
 }
 
+/// The glyphs of `ufo`'s active layer that match the search string and Unicode block filter, in
+/// font order.
+pub(super) fn visible_glyphs<'u>(
+    ufo: &'u UFO,
+    filter_string: &str,
+    filter_block: &Option<String>,
+) -> Vec<&'u GlyphEntry> {
+    let filtered_vec: Vec<&GlyphEntry> =
+        filter_glyphs(ufo.visible_entries(), &filter_string.to_lowercase());
+
+    let filtered_set: HashSet<_> = filtered_vec.into_iter().cloned().collect();
+
+    let visible_set: HashSet<_> = if let Some(block_name) = &filter_block {
+        ufo.unicode_blocks
+            .iter()
+            .find(|block| block.name == *block_name)
+            .map(|block| {
+                let block_set: HashSet<String> =
+                    block.glyphs.iter().map(|x| x.name.clone()).collect();
+                filtered_set
+                    .iter()
+                    .filter(|x| block_set.contains(&x.glifname))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_else(HashSet::new)
+    } else {
+        filtered_set
+    };
+
+    ufo.visible_entries()
+        .iter()
+        .filter(|entry| visible_set.contains(entry))
+        .collect()
+}
 
 fn filter_glyphs<'a>(glyph_entries: &'a [GlyphEntry], query: &str) -> Vec<&'a GlyphEntry> {
     let matcher = SkimMatcherV2::default();
@@ -1,5 +1,7 @@
+use std::path::PathBuf;
+
 use super::filedialog;
-use crate::{interface::Interface, viewer::UFOViewer};
+use crate::{designspace, interface::Interface, viewer::UFOViewer};
 
 pub fn menu(ctx: &egui::Context, viewer: &mut UFOViewer, interface: &mut Interface) {
     egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
@@ -7,32 +9,76 @@ pub fn menu(ctx: &egui::Context, viewer: &mut UFOViewer, interface: &mut Interfa
             ui.menu_button("File", |ui| {
                 if ui.button("Open").clicked() {
                     match filedialog::open_folder(None) {
-                        Some(f) => {
-                            viewer.set_font(&f);
-                            let font = viewer.get_active_master().unwrap();
-                            interface
-                                .set_window_title(
-                                    format!("MFEKUFO — {0}", font.metadata.name).as_str(),
-                                )
-                                .expect("Failed to set window title!");
-                        }
+                        Some(f) => open_font(viewer, interface, &f),
+                        None => {}
+                    };
+                }
+
+                if ui.button("Open UFOZ").clicked() {
+                    match filedialog::open_archive(None) {
+                        Some(f) => open_font(viewer, interface, &f),
+                        None => {}
+                    };
+                }
+
+                if ui.button("Open Designspace").clicked() {
+                    match filedialog::open_designspace(None) {
+                        Some(f) => open_font(viewer, interface, &f),
                         None => {}
                     };
                 }
 
                 if viewer.get_active_master().is_some() && ui.button("Add Master").clicked() {
                     match filedialog::open_folder(None) {
-                        Some(f) => {
-                            viewer.add_master(&f);
-                        }
+                        Some(f) => add_master(viewer, &f),
                         None => {}
                     }; 
                 }
 
+                if viewer.get_active_master().is_some() && ui.button("Add UFOZ Master").clicked() {
+                    match filedialog::open_archive(None) {
+                        Some(f) => add_master(viewer, &f),
+                        None => {}
+                    };
+                }
+
+                if viewer.has_archives() && ui.button("Save UFOZ").clicked() {
+                    viewer.save_archives();
+                }
+
                 if ui.button("Exit").clicked() {
                     viewer.exit();
                 }
             });
+
+            if !viewer.masters.is_empty() {
+                ui.menu_button("Font", |ui| {
+                    ui.menu_button("Font Info", |ui| {
+                        for idx in 0..viewer.masters.len() {
+                            let name = viewer.masters[idx].display_name().to_owned();
+                            if ui.button(name).clicked() {
+                                viewer.open_fontinfo(idx);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+
+                    if ui.button("Designspace").clicked() {
+                        viewer.open_designspace_editor();
+                        ui.close_menu();
+                    }
+
+                    if viewer.masters.len() > 1 && ui.button("Instance Preview").clicked() {
+                        viewer.open_instance_preview();
+                        ui.close_menu();
+                    }
+
+                    if viewer.masters.len() > 1 && ui.button("Interpolation Report").clicked() {
+                        viewer.open_report();
+                        ui.close_menu();
+                    }
+                });
+            }
         });
 
         let mut filter_string = viewer.filter_string.clone();
@@ -40,12 +86,76 @@ pub fn menu(ctx: &egui::Context, viewer: &mut UFOViewer, interface: &mut Interfa
             ui.label("Search:");
             ui.text_edit_singleline(&mut filter_string);
 
-            if let Some(check) = &viewer.interpolation_check {
-                if !check.succeeded {
-                    ui.label("Interpolation errors found!");
-                }
+            let failed = matches!(&viewer.interpolation_check, Some(check) if !check.succeeded);
+            if failed && ui.button("Interpolation errors found!").clicked() {
+                viewer.open_report();
             }
         });
         viewer.filter_string = filter_string;
     });
+
+    load_errors_window(ctx, viewer);
+}
+
+/// Opens a UFO, .ufoz or .designspace, replacing whatever is open.
+pub fn open_font(viewer: &mut UFOViewer, interface: &mut Interface, path: &PathBuf) {
+    let opened = if designspace::is_designspace(path) {
+        viewer.open_designspace(path)
+    } else {
+        viewer.set_font(path)
+    };
+
+    match opened {
+        Ok(()) => {
+            let title = match &viewer.designspace {
+                Some(_) => path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+                None => viewer.get_active_master().unwrap().metadata.name.clone(),
+            };
+            interface
+                .set_window_title(format!("MFEKUFO — {0}", title).as_str())
+                .expect("Failed to set window title!");
+        }
+        Err(e) => viewer.load_errors.push(e),
+    }
+}
+
+pub fn add_master(viewer: &mut UFOViewer, path: &PathBuf) {
+    if let Err(e) = viewer.add_master(path) {
+        viewer.load_errors.push(e);
+    }
+}
+
+fn load_errors_window(ctx: &egui::Context, viewer: &mut UFOViewer) {
+    if viewer.load_errors.is_empty() {
+        return;
+    }
+
+    let mut dismissed = false;
+    egui::Window::new("Errors")
+        .collapsible(false)
+        .resizable(true)
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical().max_height(300.).show(ui, |ui| {
+                for error in &viewer.load_errors {
+                    match error.path() {
+                        Some(path) => {
+                            ui.strong(path.display().to_string());
+                            ui.label(error.to_string());
+                        }
+                        None => {
+                            ui.label(error.to_string());
+                        }
+                    }
+                    ui.separator();
+                }
+            });
+
+            if ui.button("OK").clicked() {
+                dismissed = true;
+            }
+        });
+
+    if dismissed {
+        viewer.load_errors.clear();
+    }
 }
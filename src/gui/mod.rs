@@ -1,4 +1,8 @@
+pub mod designspace;
 pub mod filedialog;
+pub mod fontinfo;
 pub mod fontview;
+pub mod instance;
 pub mod menu;
-pub mod mastertab;
\ No newline at end of file
+pub mod mastertab;
+pub mod report;
\ No newline at end of file
@@ -0,0 +1,463 @@
+use std::process::Command;
+
+use egui::Color32;
+
+use crate::{
+    interpolation::{
+        correspondence::{self, ContourMatch},
+        GlyphReport, InterpolationCheckResults,
+    },
+    parsing::glyph_entries::GlyphEntry,
+    ufo_cache::{overlay_color, UFOCache},
+    viewer::UFO,
+};
+
+/// State of the Interpolation Report tab.
+#[derive(Debug, Default)]
+pub struct ReportView {
+    // whether to draw every master's outline over each other under each glyph
+    pub overlay: bool,
+    // the master fixes match the others to and missing glyphs are copied from; by default the
+    // first one that has the glyph
+    reference: Option<usize>,
+    // whether copied glyphs are scaled to the vertical metrics of the master they're copied to
+    scale_copies: bool,
+    pub status: Option<String>,
+}
+
+/// Something the report asks of the viewer, which it can't do itself while the tabs are drawn.
+#[derive(Debug, Clone)]
+pub enum ReportAction {
+    /// Rewrite `glifname` in the `targets` masters so that its contours line up with the
+    /// `reference` master's.
+    MatchContours {
+        glifname: String,
+        reference: usize,
+        targets: Vec<usize>,
+    },
+    /// Copy the glyphs other masters lack from the `reference` master.
+    SyncGlyphSets { reference: usize, scale: bool },
+    /// Take `glifname` off master `master`'s list of copies to review.
+    MarkReviewed { master: usize, glifname: String },
+    /// Mark master `master` as sparse, or not.
+    SetSparse { master: usize, sparse: bool },
+}
+
+/// Lists every glyph that won't interpolate and what the masters disagree on. Clicking "Show"
+/// asks for the grid to be filtered down to the glyph, through `filter_request`; fixes are asked
+/// for through `action`.
+pub fn report_ui(
+    ui: &mut egui::Ui,
+    results: Option<&InterpolationCheckResults>,
+    masters: &[UFO],
+    ufo_cache: &mut UFOCache,
+    view: &mut ReportView,
+    filter_request: &mut Option<String>,
+    action: &mut Option<ReportAction>,
+) {
+    let results = match results {
+        Some(results) => results,
+        None => {
+            ui.label("Open at least two masters to check them for compatibility.");
+            return;
+        }
+    };
+    review_ui(ui, masters, filter_request, action);
+    sparse_ui(ui, masters, action);
+    if results.succeeded {
+        ui.label(format!(
+            "All {} glyphs are compatible across {} masters.",
+            results.glyph_set.len(),
+            masters.len()
+        ));
+    } else {
+        ui.label(format!(
+            "{} of {} glyphs are incompatible.",
+            results.combined.len(),
+            results.glyph_set.len()
+        ));
+    }
+    if !results.warnings.is_empty() {
+        ui.colored_label(
+            Color32::YELLOW,
+            format!(
+                "{} compatible glyphs seem to have contours out of order, reversed or starting \
+                 at a different point.",
+                results.warnings.len()
+            ),
+        );
+    }
+    if results.reports.is_empty() {
+        return;
+    }
+    // a master chosen before other masters were opened may be gone
+    view.reference = view.reference.filter(|&mi| mi < masters.len());
+    reference_combo(ui, masters, &mut view.reference);
+    sync_ui(ui, masters, results, view, action);
+    ui.checkbox(&mut view.overlay, "Overlay the masters")
+        .on_hover_text("Lines join the points that get interpolated with each other");
+    if let Some(status) = &view.status {
+        ui.label(status);
+    }
+    ui.separator();
+
+    egui::ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            for (name, report) in &results.reports {
+                egui::CollapsingHeader::new(name)
+                    .id_source(("report", name))
+                    .show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            if ui.button("Show").clicked() {
+                                *filter_request = Some(report.glifname.clone());
+                            }
+                            open_buttons(ui, masters, report);
+                        });
+                        if results.warnings.contains(name) {
+                            ui.colored_label(
+                                Color32::YELLOW,
+                                "Interpolates, but its contours may not be paired up as intended",
+                            );
+                        }
+                        if view.overlay {
+                            overlay_ui(ui, masters, ufo_cache, report);
+                        }
+                        glyph_report_ui(ui, name, masters, report);
+                        fix_ui(ui, masters, report, view.reference, action);
+                    });
+            }
+        });
+}
+
+// One button per master that has the glyph.
+fn open_buttons(ui: &mut egui::Ui, masters: &[UFO], report: &GlyphReport) {
+    for (master, filename) in masters.iter().zip(&report.filenames) {
+        if let Some(filename) = filename {
+            if ui
+                .button(format!("Open {}", master.display_name()))
+                .on_hover_text("Edit in MFEKglif")
+                .clicked()
+            {
+                Command::new("MFEKglif")
+                    .arg(filename)
+                    .spawn()
+                    .expect("Couldn't open MFEKglif! Is it installed?");
+            }
+        }
+    }
+}
+
+fn glyph_report_ui(ui: &mut egui::Ui, name: &str, masters: &[UFO], report: &GlyphReport) {
+    let missing = report.missing_in();
+    if !missing.is_empty() {
+        let names: Vec<&str> = missing.iter().map(|&i| masters[i].display_name()).collect();
+        ui.colored_label(Color32::RED, format!("Missing in {}", names.join(", ")));
+    }
+
+    egui::Grid::new(("report grid", name))
+        .num_columns(masters.len() + 1)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("");
+            for master in masters {
+                ui.strong(master.display_name());
+            }
+            ui.end_row();
+
+            row(ui, "Contours", &report.contour_counts, |c| c.to_string());
+            if !report.components.is_empty() {
+                row(ui, "Components", &report.components, String::clone);
+            }
+            if !report.anchors.is_empty() {
+                row(ui, "Anchors", &report.anchors, String::clone);
+            }
+            for (ci, counts) in &report.point_counts {
+                row(ui, &format!("Contour {} points", ci), counts, |c| c.to_string());
+            }
+            for (ci, states) in &report.open_states {
+                row(ui, &format!("Contour {}", ci), states, |&open| {
+                    if open { "open" } else { "closed" }.to_string()
+                });
+            }
+            for (ci, types) in &report.point_types {
+                row(ui, &format!("Contour {} point types", ci), types, String::clone);
+            }
+            for (ci, segments) in &report.segments {
+                row(ui, &format!("Contour {} handles", ci), segments, String::clone);
+            }
+        });
+
+    if !report.point_types.is_empty() || !report.segments.is_empty() {
+        ui.weak(
+            "Point types: M move, L line, C curve, Q quadratic, O off-curve. \
+             Handles: . none, < incoming, > outgoing, = both.",
+        );
+    }
+}
+
+// Offers to copy the glyphs some masters lack from the reference master.
+fn sync_ui(
+    ui: &mut egui::Ui,
+    masters: &[UFO],
+    results: &InterpolationCheckResults,
+    view: &mut ReportView,
+    action: &mut Option<ReportAction>,
+) {
+    if results.missing_glyphs.is_empty() {
+        return;
+    }
+
+    let reference = view.reference.unwrap_or(0);
+    let reference_master = match masters.get(reference) {
+        Some(master) => master,
+        None => return,
+    };
+    let missing: usize = results
+        .missing_by_master
+        .iter()
+        .enumerate()
+        .filter(|(mi, _)| *mi != reference)
+        .map(|(_, missing)| {
+            missing
+                .iter()
+                .filter(|name| reference_master.get(name).is_some())
+                .count()
+        })
+        .sum();
+
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(
+                missing > 0,
+                egui::Button::new(format!(
+                    "Copy {} missing glyphs from {}",
+                    missing,
+                    reference_master.display_name()
+                )),
+            )
+            .on_hover_text("Copies are marked for review")
+            .clicked()
+        {
+            *action = Some(ReportAction::SyncGlyphSets {
+                reference,
+                scale: view.scale_copies,
+            });
+        }
+        ui.checkbox(&mut view.scale_copies, "Scale to each master's vertical metrics");
+    });
+}
+
+// Copies made by `sync_ui` stay listed here until they're marked as reviewed.
+fn review_ui(
+    ui: &mut egui::Ui,
+    masters: &[UFO],
+    filter_request: &mut Option<String>,
+    action: &mut Option<ReportAction>,
+) {
+    for (mi, master) in masters.iter().enumerate() {
+        if master.needs_review.is_empty() {
+            continue;
+        }
+
+        egui::CollapsingHeader::new(format!(
+            "Copied glyphs to review in {} ({})",
+            master.display_name(),
+            master.needs_review.len()
+        ))
+        .id_source(("review", mi))
+        .show(ui, |ui| {
+            for glifname in &master.needs_review {
+                ui.horizontal(|ui| {
+                    ui.label(glifname);
+                    if ui.button("Show").clicked() {
+                        *filter_request = Some(glifname.clone());
+                    }
+                    if let Some(entry) = master.get(glifname) {
+                        if ui.button("Open").on_hover_text("Edit in MFEKglif").clicked() {
+                            Command::new("MFEKglif")
+                                .arg(&entry.filename)
+                                .spawn()
+                                .expect("Couldn't open MFEKglif! Is it installed?");
+                        }
+                    }
+                    if ui.button("Reviewed").clicked() {
+                        *action = Some(ReportAction::MarkReviewed {
+                            master: mi,
+                            glifname: glifname.clone(),
+                        });
+                    }
+                });
+            }
+        });
+    }
+}
+
+// Sparse masters may lack glyphs without them being reported missing. Masters read from a
+// designspace layer always are.
+fn sparse_ui(ui: &mut egui::Ui, masters: &[UFO], action: &mut Option<ReportAction>) {
+    let count = masters.iter().filter(|m| m.is_sparse()).count();
+    egui::CollapsingHeader::new(format!("Sparse masters ({})", count))
+        .id_source("sparse masters")
+        .show(ui, |ui| {
+            for (mi, master) in masters.iter().enumerate() {
+                let mut sparse = master.is_sparse();
+                let response = match &master.source_layer {
+                    Some(layer) => ui
+                        .add_enabled(false, egui::Checkbox::new(&mut sparse, master.display_name()))
+                        .on_disabled_hover_text(format!("Reads layer {} of its UFO", layer)),
+                    None => ui
+                        .checkbox(&mut sparse, master.display_name())
+                        .on_hover_text("Saved in its lib.plist"),
+                };
+                if response.changed() {
+                    *action = Some(ReportAction::SetSparse { master: mi, sparse });
+                }
+            }
+        });
+}
+
+fn overlay_ui(ui: &mut egui::Ui, masters: &[UFO], ufo_cache: &mut UFOCache, report: &GlyphReport) {
+    let present: Vec<(usize, &GlyphEntry)> = masters
+        .iter()
+        .enumerate()
+        .filter_map(|(mi, master)| Some((mi, master.get(&report.glifname)?)))
+        .collect();
+
+    // colors follow the masters that have the glyph, the way the overlay is drawn
+    ui.horizontal_wrapped(|ui| {
+        for (i, (mi, _)) in present.iter().enumerate() {
+            ui.colored_label(overlay_color(i), masters[*mi].display_name());
+        }
+    });
+
+    let entries = present.iter().map(|(_, entry)| (*entry).clone()).collect();
+    let image = ufo_cache.get_overlay_image_handle(&report.glifname, entries);
+    ui.image(image, [256., 256.]);
+}
+
+fn reference_combo(ui: &mut egui::Ui, masters: &[UFO], reference: &mut Option<usize>) {
+    ui.horizontal(|ui| {
+        ui.label("Reference master");
+        egui::ComboBox::from_id_source("report reference")
+            .selected_text(match reference.and_then(|mi| masters.get(mi)) {
+                Some(master) => master.display_name(),
+                None => "the first master with the glyph",
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(reference, None, "the first master with the glyph");
+                for (mi, master) in masters.iter().enumerate() {
+                    ui.selectable_value(reference, Some(mi), master.display_name());
+                }
+            });
+    });
+}
+
+// Describes which masters' contours seem reordered, reversed or restarted compared to the
+// reference, and offers to rewrite them.
+fn fix_ui(
+    ui: &mut egui::Ui,
+    masters: &[UFO],
+    report: &GlyphReport,
+    reference: Option<usize>,
+    action: &mut Option<ReportAction>,
+) {
+    let reference = reference
+        .filter(|&mi| masters.get(mi).is_some_and(|m| m.get(&report.glifname).is_some()))
+        .unwrap_or(report.reference);
+    let (reference_master, reference_entry) = match masters
+        .get(reference)
+        .and_then(|m| Some((m, m.get(&report.glifname)?)))
+    {
+        Some(found) => found,
+        None => return,
+    };
+
+    let suggestions: Vec<(usize, Vec<ContourMatch>)> = masters
+        .iter()
+        .enumerate()
+        .filter(|(mi, _)| *mi != reference)
+        .filter_map(|(mi, master)| {
+            let entry = master.get(&report.glifname)?;
+            let matches = correspondence::suggest(&reference_entry.glif, &entry.glif)?;
+            let identity = matches.iter().enumerate().all(|(ci, m)| m.is_identity(ci));
+            (!identity).then(|| (mi, matches))
+        })
+        .collect();
+    if suggestions.is_empty() {
+        return;
+    }
+
+    let reference_name = reference_master.display_name();
+    for (mi, matches) in &suggestions {
+        ui.label(format!(
+            "Compared to {}, {} seems to have:",
+            reference_name,
+            masters[*mi].display_name()
+        ));
+        for (ci, m) in matches.iter().enumerate() {
+            if !m.is_identity(ci) {
+                ui.label(format!("    {}", describe_match(ci, m)));
+            }
+        }
+    }
+
+    let request = |targets: Vec<usize>| ReportAction::MatchContours {
+        glifname: report.glifname.clone(),
+        reference,
+        targets,
+    };
+    ui.horizontal(|ui| {
+        for (mi, _) in &suggestions {
+            if ui
+                .button(format!("Fix {}", masters[*mi].display_name()))
+                .on_hover_text(format!("Rewrite its contours to match {} and save it", reference_name))
+                .clicked()
+            {
+                *action = Some(request(vec![*mi]));
+            }
+        }
+        if suggestions.len() > 1 && ui.button("Fix all").clicked() {
+            *action = Some(request(suggestions.iter().map(|(mi, _)| *mi).collect()));
+        }
+    });
+}
+
+fn describe_match(ci: usize, m: &ContourMatch) -> String {
+    let mut changes = Vec::new();
+    if m.contour != ci {
+        changes.push(format!("moved to contour {}", m.contour));
+    }
+    if m.reversed {
+        changes.push("reversed".to_string());
+    }
+    if m.start != 0 {
+        changes.push(format!("starting at its point {}", m.start));
+    }
+    format!("contour {} {}", ci, changes.join(", "))
+}
+
+// A row of per-master values, with values that differ from the first master's in red.
+fn row<T: PartialEq>(
+    ui: &mut egui::Ui,
+    label: &str,
+    values: &[Option<T>],
+    show: impl Fn(&T) -> String,
+) {
+    ui.label(label);
+    let first = values.iter().flatten().next();
+    for value in values {
+        match value {
+            Some(value) if Some(value) != first => {
+                ui.colored_label(Color32::RED, show(value));
+            }
+            Some(value) => {
+                ui.label(show(value));
+            }
+            None => {
+                ui.weak("—");
+            }
+        }
+    }
+    ui.end_row();
+}
@@ -0,0 +1,501 @@
+use std::path::PathBuf;
+
+use glifparser::{contour::State, Glif, Handle, Point};
+use MFEKmath::Vector;
+
+use crate::{
+    error::LoadError,
+    parsing::glyph_entries::{is_glifjson, GlyphEntry},
+};
+
+/// Where a contour of the reference master went in another master.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContourMatch {
+    // index of the contour in the other master
+    pub contour: usize,
+    // whether it runs the other way round
+    pub reversed: bool,
+    // index of the point, once reversed, that corresponds to the reference contour's first point
+    pub start: usize,
+}
+
+impl ContourMatch {
+    pub fn is_identity(&self, ci: usize) -> bool {
+        self.contour == ci && !self.reversed && self.start == 0
+    }
+}
+
+// A rotated start point has to fit at least this much better than the current one to be flagged,
+// so that nearly symmetrical contours like an o's counter don't flag on noise.
+const START_POINT_MARGIN: f64 = 0.5;
+// Likewise a different pairing of the contours has to move them this much less than the current
+// order does, so that contours whose centroids nearly coincide don't swap on noise.
+const ORDER_MARGIN: f64 = 0.5;
+// The cost of pairing contours that can't correspond, far above any real distance between two
+// centroids scaled into the bounding box.
+const UNFIT: f64 = 1e9;
+
+/// Guesses which contour of `other` corresponds to each contour of `reference`, which way round
+/// it runs and where it starts, by comparing their shapes. Both glyphs are scaled to their
+/// bounding boxes first, so that weight and width differences between the masters don't matter.
+/// None if the glyphs can't correspond at all, e.g. because their contour counts differ.
+pub fn suggest(reference: &Glif<()>, other: &Glif<()>) -> Option<Vec<ContourMatch>> {
+    let empty = Vec::new();
+    let ref_outline = reference.outline.as_ref().unwrap_or(&empty);
+    let other_outline = other.outline.as_ref().unwrap_or(&empty);
+    if ref_outline.len() != other_outline.len() {
+        return None;
+    }
+
+    let ref_shapes = normalized_shapes(ref_outline);
+    let other_shapes = normalized_shapes(other_outline);
+
+    // pair up the contours so that their centroids move as little as possible overall, but
+    // only move away from the current order if that's clearly better
+    let cost: Vec<Vec<f64>> = ref_shapes
+        .iter()
+        .map(|shape| {
+            other_shapes
+                .iter()
+                .map(|candidate| {
+                    if candidate.fits(shape) {
+                        shape.centroid.distance(candidate.centroid)
+                    } else {
+                        UNFIT
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let total = |pairs: &[usize]| {
+        pairs
+            .iter()
+            .enumerate()
+            .map(|(ci, &oi)| cost[ci][oi])
+            .sum::<f64>()
+    };
+    let identity: Vec<usize> = (0..ref_shapes.len()).collect();
+    let optimal = min_cost_assignment(&cost);
+    let pairs = if total(&identity) < UNFIT && total(&optimal) >= total(&identity) * ORDER_MARGIN {
+        identity
+    } else {
+        optimal
+    };
+    if total(&pairs) >= UNFIT {
+        return None;
+    }
+
+    let mut matches = Vec::with_capacity(ref_shapes.len());
+    for (shape, &best) in ref_shapes.iter().zip(&pairs) {
+        let candidate = &other_shapes[best];
+        let reversed = !shape.open && shape.clockwise() != candidate.clockwise();
+        let points = if reversed {
+            candidate.reversed_points()
+        } else {
+            candidate.points.clone()
+        };
+        let start = if shape.open {
+            0
+        } else {
+            best_start(&shape.points, &points)
+        };
+
+        matches.push(ContourMatch {
+            contour: best,
+            reversed,
+            start,
+        });
+    }
+
+    Some(matches)
+}
+
+/// Reorders, reverses and restarts the contours of `glif` as `matches` says they correspond to
+/// the reference's.
+pub fn apply(glif: &mut Glif<()>, matches: &[ContourMatch]) {
+    let outline = match &glif.outline {
+        Some(outline) => outline,
+        None => return,
+    };
+
+    let rearranged = matches
+        .iter()
+        .map(|m| {
+            let mut contour = outline[m.contour].clone();
+            if m.reversed {
+                contour = reversed(contour);
+            }
+            contour.rotate_left(m.start);
+            contour
+        })
+        .collect();
+    glif.outline = Some(rearranged);
+}
+
+/// Rewrites `target`'s .glif so that its contours line up with `reference`, and returns the new
+/// glyph. None if they already line up.
+pub fn rewrite(reference: &Glif<()>, target: &GlyphEntry) -> Result<Option<Glif<()>>, LoadError> {
+    let path = PathBuf::from(&target.filename);
+    let error = |message: String| LoadError::Glif {
+        path: path.clone(),
+        message,
+    };
+    if is_glifjson(&path) {
+        return Err(error("layered .glifjson glyphs can't be rewritten, edit them in MFEKglif".to_string()));
+    }
+
+    let mut glif = target.glif.clone();
+    let matches = match suggest(reference, &glif) {
+        Some(matches) => matches,
+        None => return Err(error("the contours don't correspond to the reference master's".to_string())),
+    };
+    if matches.iter().enumerate().all(|(ci, m)| m.is_identity(ci)) {
+        return Ok(None);
+    }
+
+    apply(&mut glif, &matches);
+    glifparser::write_to_filename(&glif, &path).map_err(|e| error(e.to_string()))?;
+    Ok(Some(glif))
+}
+
+// Runs the contour the other way. Each point's type describes the segment leading to it, so
+// types move over by one along with the handles swapping sides.
+fn reversed(contour: Vec<Point<()>>) -> Vec<Point<()>> {
+    let n = contour.len();
+    let open = contour.is_open();
+
+    (0..n)
+        .map(|k| {
+            let (i, ptype) = if open {
+                (n - 1 - k, if k == 0 { contour[0].ptype } else { contour[n - k].ptype })
+            } else {
+                ((n - k) % n, contour[(n - k + 1) % n].ptype)
+            };
+            let point = &contour[i];
+            Point {
+                a: point.b,
+                b: point.a,
+                ptype,
+                ..point.clone()
+            }
+        })
+        .collect()
+}
+
+// A contour with every point and handle scaled into the glyph's bounding box.
+struct Shape {
+    open: bool,
+    // (on-curve, handle towards the previous point, handle towards the next point)
+    points: Vec<(Vector, Vector, Vector)>,
+    centroid: Vector,
+}
+
+impl Shape {
+    fn fits(&self, other: &Shape) -> bool {
+        self.open == other.open && self.points.len() == other.points.len()
+    }
+
+    // Shoelace formula over the control polygon, which has the same orientation as the curve.
+    fn clockwise(&self) -> bool {
+        let polygon: Vec<Vector> = self
+            .points
+            .iter()
+            .flat_map(|&(on, b, a)| [b, on, a])
+            .collect();
+        let area: f64 = polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .map(|(p, q)| p.x * q.y - q.x * p.y)
+            .sum();
+        area < 0.
+    }
+
+    // Closed contours keep their first point when reversed, as glifparser does it.
+    fn reversed_points(&self) -> Vec<(Vector, Vector, Vector)> {
+        let n = self.points.len();
+        (0..n)
+            .map(|k| if self.open { n - 1 - k } else { (n - k) % n })
+            .map(|i| {
+                let (on, b, a) = self.points[i];
+                (on, a, b)
+            })
+            .collect()
+    }
+}
+
+fn normalized_shapes(outline: &[Vec<Point<()>>]) -> Vec<Shape> {
+    let on_curve = outline.iter().flatten();
+    let min_x = on_curve.clone().map(|p| p.x).fold(f32::INFINITY, f32::min) as f64;
+    let max_x = on_curve.clone().map(|p| p.x).fold(f32::NEG_INFINITY, f32::max) as f64;
+    let min_y = on_curve.clone().map(|p| p.y).fold(f32::INFINITY, f32::min) as f64;
+    let max_y = on_curve.map(|p| p.y).fold(f32::NEG_INFINITY, f32::max) as f64;
+    let (width, height) = ((max_x - min_x).max(1.), (max_y - min_y).max(1.));
+
+    let scale = |x: f32, y: f32| {
+        Vector::from_components((x as f64 - min_x) / width, (y as f64 - min_y) / height)
+    };
+    let handle = |handle: Handle, p: &Point<()>| match handle {
+        Handle::At(hx, hy) => scale(hx, hy),
+        Handle::Colocated => scale(p.x, p.y),
+    };
+
+    outline
+        .iter()
+        .map(|contour| {
+            let points: Vec<_> = contour
+                .iter()
+                .map(|p| (scale(p.x, p.y), handle(p.b, p), handle(p.a, p)))
+                .collect();
+            let n = points.len().max(1) as f64;
+            let centroid = Vector::from_components(
+                points.iter().map(|p| p.0.x).sum::<f64>() / n,
+                points.iter().map(|p| p.0.y).sum::<f64>() / n,
+            );
+
+            Shape {
+                open: contour.is_open(),
+                points,
+                centroid,
+            }
+        })
+        .collect()
+}
+
+// The Hungarian algorithm: for each row of the square `cost` matrix, the column it's assigned to
+// so that the sum of the chosen costs is as small as possible.
+fn min_cost_assignment(cost: &[Vec<f64>]) -> Vec<usize> {
+    let n = cost.len();
+    // potentials of the rows and columns, and which row each column is assigned to, all 1-based
+    // with column 0 standing for the row being added
+    let mut u = vec![0.; n + 1];
+    let mut v = vec![0.; n + 1];
+    let mut row_of = vec![0; n + 1];
+    let mut way = vec![0; n + 1];
+
+    for row in 1..=n {
+        row_of[0] = row;
+        let mut col = 0;
+        let mut min_slack = vec![f64::INFINITY; n + 1];
+        let mut visited = vec![false; n + 1];
+        loop {
+            visited[col] = true;
+            let r = row_of[col];
+            let (mut delta, mut next) = (f64::INFINITY, 0);
+            for c in (1..=n).filter(|&c| !visited[c]) {
+                let slack = cost[r - 1][c - 1] - u[r] - v[c];
+                if slack < min_slack[c] {
+                    min_slack[c] = slack;
+                    way[c] = col;
+                }
+                if min_slack[c] < delta {
+                    delta = min_slack[c];
+                    next = c;
+                }
+            }
+            for c in 0..=n {
+                if visited[c] {
+                    u[row_of[c]] += delta;
+                    v[c] -= delta;
+                } else {
+                    min_slack[c] -= delta;
+                }
+            }
+            col = next;
+            if row_of[col] == 0 {
+                break;
+            }
+        }
+        // flip the augmenting path
+        while col != 0 {
+            let prev = way[col];
+            row_of[col] = row_of[prev];
+            col = prev;
+        }
+    }
+
+    let mut assignment = vec![0; n];
+    for c in 1..=n {
+        assignment[row_of[c] - 1] = c - 1;
+    }
+    assignment
+}
+
+// The rotation of `points` that lies closest to `reference`, if it's clearly better than none.
+fn best_start(reference: &[(Vector, Vector, Vector)], points: &[(Vector, Vector, Vector)]) -> usize {
+    let n = points.len();
+    let cost = |start: usize| -> f64 {
+        reference
+            .iter()
+            .enumerate()
+            .map(|(i, r)| {
+                let p = points[(i + start) % n];
+                r.0.distance(p.0) + r.1.distance(p.1) + r.2.distance(p.2)
+            })
+            .sum()
+    };
+
+    let current = cost(0);
+    let (best, best_cost) = (1..n)
+        .map(|start| (start, cost(start)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, current));
+
+    if best_cost < current * START_POINT_MARGIN {
+        best
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glifparser::PointType;
+
+    fn point(x: f32, y: f32, ptype: PointType) -> Point<()> {
+        Point::from_x_y_type((x, y), ptype)
+    }
+
+    fn glif(outline: Vec<Vec<Point<()>>>) -> Glif<()> {
+        let mut glif = Glif::new();
+        glif.outline = Some(outline);
+        glif
+    }
+
+    // Counter-clockwise, starting at the bottom left corner.
+    fn square(cx: f32, cy: f32, half: f32) -> Vec<Point<()>> {
+        vec![
+            point(cx - half, cy - half, PointType::Line),
+            point(cx + half, cy - half, PointType::Line),
+            point(cx + half, cy + half, PointType::Line),
+            point(cx - half, cy + half, PointType::Line),
+        ]
+    }
+
+    // Counter-clockwise, with the first point `degrees` round from the positive x axis.
+    fn octagon(degrees: f32) -> Vec<Point<()>> {
+        (0..8)
+            .map(|k| {
+                let angle = (degrees + 45. * k as f32).to_radians();
+                point(100. * angle.cos(), 100. * angle.sin(), PointType::Line)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reversing_closed_contour_keeps_first_point() {
+        let mut contour = vec![
+            point(0., 0., PointType::Line),
+            point(100., 0., PointType::Curve),
+            point(100., 100., PointType::Line),
+        ];
+        contour[1].b = Handle::At(60., -20.);
+        contour[1].a = Handle::At(120., 40.);
+
+        let reversed = reversed(contour);
+        let coordinates: Vec<_> = reversed.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(coordinates, [(0., 0.), (100., 100.), (100., 0.)]);
+        // the curve led to (100, 0), so now it leads away from it, to (0, 0)
+        let ptypes: Vec<_> = reversed.iter().map(|p| p.ptype).collect();
+        assert_eq!(ptypes, [PointType::Curve, PointType::Line, PointType::Line]);
+        assert_eq!(reversed[2].a, Handle::At(60., -20.));
+        assert_eq!(reversed[2].b, Handle::At(120., 40.));
+    }
+
+    #[test]
+    fn reversing_open_contour_starts_at_its_end() {
+        let contour = vec![
+            point(0., 0., PointType::Move),
+            point(100., 0., PointType::Line),
+            point(100., 100., PointType::Curve),
+        ];
+
+        let reversed = reversed(contour);
+        let coordinates: Vec<_> = reversed.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(coordinates, [(100., 100.), (100., 0.), (0., 0.)]);
+        let ptypes: Vec<_> = reversed.iter().map(|p| p.ptype).collect();
+        assert_eq!(ptypes, [PointType::Move, PointType::Curve, PointType::Line]);
+    }
+
+    #[test]
+    fn assignment_beats_identity() {
+        let cost = vec![vec![4., 1., 3.], vec![2., 0., 5.], vec![3., 2., 2.]];
+        assert_eq!(min_cost_assignment(&cost), [1, 0, 2]);
+    }
+
+    #[test]
+    fn matching_contours_match_themselves() {
+        let reference = glif(vec![square(50., 50., 50.), square(50., 50., 20.)]);
+        let matches = suggest(&reference, &reference.clone()).unwrap();
+        assert!(matches.iter().enumerate().all(|(ci, m)| m.is_identity(ci)));
+    }
+
+    #[test]
+    fn contour_counts_must_agree() {
+        let reference = glif(vec![square(50., 50., 50.), square(50., 50., 20.)]);
+        let other = glif(vec![square(50., 50., 50.)]);
+        assert_eq!(suggest(&reference, &other), None);
+    }
+
+    #[test]
+    fn reversed_contour_is_flagged() {
+        let reference = glif(vec![square(50., 50., 50.)]);
+        let other = glif(vec![reversed(square(50., 50., 50.))]);
+        let matches = suggest(&reference, &other).unwrap();
+        assert_eq!(
+            matches,
+            [ContourMatch {
+                contour: 0,
+                reversed: true,
+                start: 0,
+            }]
+        );
+
+        let mut fixed = other.clone();
+        apply(&mut fixed, &matches);
+        assert_eq!(fixed.outline, reference.outline);
+    }
+
+    #[test]
+    fn swapped_contours_are_flagged() {
+        let reference = glif(vec![square(50., 50., 10.), square(50., 60., 10.)]);
+        let other = glif(vec![square(50., 60., 10.), square(50., 50., 10.)]);
+        let matches = suggest(&reference, &other).unwrap();
+        assert_eq!(matches[0].contour, 1);
+        assert_eq!(matches[1].contour, 0);
+    }
+
+    #[test]
+    fn slightly_better_order_is_not_flagged() {
+        // moving the centroids 4 + 4 instead of 6 + 6 isn't worth a reorder
+        let reference = glif(vec![square(50., 50., 10.), square(50., 60., 10.)]);
+        let other = glif(vec![square(50., 56., 10.), square(50., 54., 10.)]);
+        let matches = suggest(&reference, &other).unwrap();
+        assert!(matches.iter().enumerate().all(|(ci, m)| m.is_identity(ci)));
+    }
+
+    #[test]
+    fn moved_start_point_is_flagged() {
+        let reference = glif(vec![square(50., 50., 50.)]);
+        let mut contour = square(50., 50., 50.);
+        contour.rotate_left(1);
+        let other = glif(vec![contour]);
+        let matches = suggest(&reference, &other).unwrap();
+        assert_eq!(matches[0].start, 3);
+
+        let mut fixed = other.clone();
+        apply(&mut fixed, &matches);
+        assert_eq!(fixed.outline, reference.outline);
+    }
+
+    #[test]
+    fn start_point_moved_by_less_than_the_margin_is_not_flagged() {
+        let reference = glif(vec![octagon(0.)]);
+        // 25° off the current start but 20° off the previous point, not clearly closer
+        let matches = suggest(&reference, &glif(vec![octagon(25.)])).unwrap();
+        assert_eq!(matches[0].start, 0);
+        // 40° off the current start but 5° off the previous point
+        let matches = suggest(&reference, &glif(vec![octagon(40.)])).unwrap();
+        assert_eq!(matches[0].start, 7);
+    }
+}
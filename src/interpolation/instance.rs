@@ -0,0 +1,740 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use glifparser::{contour::State, Glif, Handle};
+use plist::{Dictionary, Value as Plist};
+use tempfile::TempDir;
+
+use super::{
+    component_signature,
+    model::{check_axes, normalize, NormalizedLocation, VariationModel},
+};
+use crate::{
+    designspace::{Designspace, Instance, Location},
+    error::LoadError,
+    parsing::layer::DEFAULT_LAYER_NAME,
+    viewer::UFO,
+};
+
+// fontinfo.plist keys that are numbers but not measurements: codes, flags and bit lists. These
+// are taken from the default master as they are.
+const NOT_INTERPOLATED: &[&str] = &[
+    "versionMajor",
+    "versionMinor",
+    "openTypeHeadFlags",
+    "openTypeHeadLowestRecPPEM",
+    "openTypeOS2Panose",
+    "openTypeOS2FamilyClass",
+    "openTypeOS2Type",
+    "openTypeOS2Selection",
+    "openTypeOS2UnicodeRanges",
+    "openTypeOS2CodePageRanges",
+    "openTypeNameRecords",
+    "postscriptWindowsCharacterSet",
+    "macintoshFONDFamilyID",
+];
+
+// Files of the default master copied into every instance unchanged.
+const COPIED_FILES: &[&str] = &["groups.plist", "features.fea", "lib.plist"];
+
+// The metainfo.plist creator of generated instances. Only UFOs with it are ever replaced.
+const CREATOR: &str = "org.MFEK.MFEKufo";
+
+/// What was written for one instance.
+#[derive(Debug)]
+pub struct InstanceReport {
+    pub path: PathBuf,
+    pub glyphs: usize,
+    // glyphs that couldn't be interpolated, and were copied from the default master instead
+    pub skipped: Vec<String>,
+}
+
+/// The part of an `Interpolator` that only depends on the axes and where the masters sit, so
+/// that it can be kept while the masters' glyphs change.
+#[derive(Debug)]
+pub struct MasterModel {
+    locations: Vec<NormalizedLocation>,
+    axis_order: Vec<String>,
+    model: VariationModel,
+    // models of only some of the masters, by their indices, built as glyphs need them
+    submodels: RefCell<HashMap<Vec<usize>, Option<VariationModel>>>,
+}
+
+impl MasterModel {
+    pub fn new(designspace: &Designspace, masters: &[UFO]) -> Result<Self, String> {
+        check_axes(&designspace.axes)?;
+        let locations: Vec<_> = masters
+            .iter()
+            .map(|m| normalize(&designspace.axes, &m.location))
+            .collect();
+        let axis_order: Vec<String> = designspace.axes.iter().map(|a| a.name.clone()).collect();
+        let model = VariationModel::new(&locations, &axis_order)?;
+
+        Ok(MasterModel {
+            locations,
+            axis_order,
+            model,
+            submodels: RefCell::new(HashMap::new()),
+        })
+    }
+}
+
+/// Blends values of the masters of a designspace at any location in it. Sparse masters only
+/// take part in the glyphs they have, the way fontTools builds a model per glyph.
+pub struct Interpolator<'a> {
+    designspace: &'a Designspace,
+    masters: &'a [UFO],
+    model: Rc<MasterModel>,
+}
+
+impl<'a> Interpolator<'a> {
+    /// `masters` must be fully loaded, with their `location`s set.
+    pub fn new(designspace: &'a Designspace, masters: &'a [UFO]) -> Result<Self, String> {
+        let model = MasterModel::new(designspace, masters)?;
+        Ok(Self::with_model(designspace, masters, Rc::new(model)))
+    }
+
+    /// Like `new`, with a model built earlier for the same axes and master locations.
+    pub fn with_model(
+        designspace: &'a Designspace,
+        masters: &'a [UFO],
+        model: Rc<MasterModel>,
+    ) -> Self {
+        Interpolator {
+            designspace,
+            masters,
+            model,
+        }
+    }
+
+    pub fn default_master(&self) -> &'a UFO {
+        &self.masters[self.default_index()]
+    }
+
+    pub fn default_index(&self) -> usize {
+        self.model.model.default_master()
+    }
+
+    pub fn weights(&self, location: &Location) -> Vec<f64> {
+        self.model
+            .model
+            .master_weights(&normalize(&self.designspace.axes, location))
+    }
+
+    // How much each of the masters at `indices` contributes at `location`, in the same order,
+    // going by a model of just those masters. None if they can't be modelled on their own, as
+    // when the default master isn't among them.
+    fn weights_among(&self, indices: &[usize], location: &Location) -> Option<Vec<f64>> {
+        let location = normalize(&self.designspace.axes, location);
+        if indices.len() == self.masters.len() {
+            return Some(self.model.model.master_weights(&location));
+        }
+
+        let mut submodels = self.model.submodels.borrow_mut();
+        let model = submodels.entry(indices.to_vec()).or_insert_with(|| {
+            let locations: Vec<_> =
+                indices.iter().map(|&i| self.model.locations[i].clone()).collect();
+            VariationModel::new(&locations, &self.model.axis_order).ok()
+        });
+        model.as_ref().map(|model| model.master_weights(&location))
+    }
+
+    /// The glyph `glifname` at `location`, blended from the masters that have it. None if a
+    /// master that isn't sparse lacks the glyph, the default master does, or the outlines don't
+    /// match up.
+    pub fn glyph(&self, glifname: &str, location: &Location) -> Option<Glif<()>> {
+        let mut present = Vec::new();
+        for (i, master) in self.masters.iter().enumerate() {
+            if master.get(glifname).is_some() {
+                present.push(i);
+            } else if !master.is_sparse() {
+                return None;
+            }
+        }
+
+        let mut weights = vec![0.; self.masters.len()];
+        for (&i, weight) in present.iter().zip(self.weights_among(&present, location)?) {
+            weights[i] = weight;
+        }
+        interpolate_glyph(self.masters, glifname, &weights, self.default_index())
+    }
+
+    /// Writes the instance to the path its `filename` gives, relative to the designspace.
+    pub fn generate(&self, instance: &Instance) -> Result<InstanceReport, LoadError> {
+        let filename = instance.filename.clone().unwrap_or_else(|| {
+            format!("instances/{}.ufo", instance.name.as_deref().unwrap_or("Instance"))
+        });
+        let path = self.designspace.base_dir().join(filename);
+        let error = |message: String| LoadError::Instance {
+            path: path.clone(),
+            message,
+        };
+
+        let location = self.designspace.full_location(&instance.location);
+        let default = self.default_master();
+
+        // fontinfo and kerning come from the full masters; sparse ones only contribute glyphs
+        let full: Vec<usize> = (0..self.masters.len())
+            .filter(|&i| !self.masters[i].is_sparse())
+            .collect();
+        let (base, weights) = full
+            .iter()
+            .position(|&i| i == self.default_index())
+            .zip(self.weights_among(&full, &location))
+            .ok_or_else(|| error("the default master can't be sparse".to_string()))?;
+
+        self.check_target(&path).map_err(error)?;
+        let staging = staging_dir(&path).map_err(error)?;
+        let dir = staging.path();
+        let glyphs_dir = dir.join("glyphs");
+        fs::create_dir_all(&glyphs_dir).map_err(|e| error(e.to_string()))?;
+
+        // glyphs
+        let mut contents: BTreeMap<String, String> = BTreeMap::new();
+        let mut skipped = Vec::new();
+        for entry in &default.glyph_entries {
+            let glif = match self.glyph(&entry.glifname, &location) {
+                Some(glif) => glif,
+                None => {
+                    skipped.push(entry.glifname.clone());
+                    entry.glif.clone()
+                }
+            };
+
+            // layered .glifjson glyphs come out flattened, as plain .glif
+            let file_name = Path::new(&entry.filename)
+                .with_extension("glif")
+                .file_name()
+                .map(|f| f.to_string_lossy().into_owned())
+                .unwrap_or_else(|| format!("{}.glif", entry.glifname));
+            glifparser::write_to_filename(&glif, glyphs_dir.join(&file_name))
+                .map_err(|e| error(format!("{}: {}", entry.glifname, e)))?;
+            contents.insert(entry.glifname.clone(), file_name);
+        }
+
+        write_plist(&glyphs_dir.join("contents.plist"), &contents).map_err(error)?;
+        write_plist(
+            &dir.join("layercontents.plist"),
+            &vec![vec![DEFAULT_LAYER_NAME, "glyphs"]],
+        )
+        .map_err(error)?;
+
+        let mut metainfo = Dictionary::new();
+        metainfo.insert("creator".to_string(), CREATOR.into());
+        metainfo.insert("formatVersion".to_string(), Plist::Integer(3.into()));
+        write_plist(&dir.join("metainfo.plist"), &metainfo).map_err(error)?;
+
+        // fontinfo
+        let raws: Vec<&Dictionary> = full
+            .iter()
+            .map(|&i| &self.masters[i].metadata.raw)
+            .collect();
+        let mut fontinfo = interpolate_dict(&raws, &weights, base);
+        name_instance(&mut fontinfo, instance);
+        write_plist(&dir.join("fontinfo.plist"), &fontinfo).map_err(error)?;
+
+        // kerning
+        let kernings: Vec<Dictionary> = full
+            .iter()
+            .map(|&i| read_kerning(&self.masters[i].path))
+            .collect::<Result<_, _>>()
+            .map_err(error)?;
+        let kerning = interpolate_kerning(&kernings, &weights);
+        if !kerning.is_empty() {
+            write_plist(&dir.join("kerning.plist"), &kerning).map_err(error)?;
+        }
+
+        for file in COPIED_FILES {
+            let source = default.path.join(file);
+            if source.is_file() {
+                fs::copy(&source, dir.join(file)).map_err(|e| error(e.to_string()))?;
+            }
+        }
+
+        replace_dir(staging, &path).map_err(error)?;
+        Ok(InstanceReport {
+            path,
+            glyphs: contents.len(),
+            skipped,
+        })
+    }
+
+    // An instance may only replace one generated earlier, never a source or an open master.
+    fn check_target(&self, path: &Path) -> Result<(), String> {
+        let target = canonical(path);
+        let sources = self.designspace.sources.iter().map(|s| self.designspace.source_path(s));
+        let masters = self
+            .masters
+            .iter()
+            .flat_map(|m| [m.path.clone(), m.source_path().to_owned()]);
+        if sources.chain(masters).any(|p| canonical(&p) == target) {
+            return Err("that's where one of the masters is".to_string());
+        }
+
+        if path.exists() && !generated_here(path) {
+            return Err("only an instance MFEKufo generated can be replaced".to_string());
+        }
+        Ok(())
+    }
+}
+
+// `path` with symlinks and `..` resolved, as far as it exists.
+fn canonical(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => canonical(parent).join(name),
+        _ => path.to_owned(),
+    }
+}
+
+fn generated_here(path: &Path) -> bool {
+    let metainfo: Option<Dictionary> = plist::from_file(path.join("metainfo.plist")).ok();
+    metainfo
+        .and_then(|m| m.get("creator").and_then(Plist::as_string).map(|c| c == CREATOR))
+        .unwrap_or(false)
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+// Instances are written to a temporary directory next to where they go, and only moved into place
+// once complete, so a failure never leaves half an instance behind.
+fn staging_dir(path: &Path) -> Result<TempDir, String> {
+    let parent = parent_dir(path);
+    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    tempfile::Builder::new()
+        .prefix(".instance")
+        .tempdir_in(parent)
+        .map_err(|e| e.to_string())
+}
+
+// Moves `staging` to `path`. An instance generated there earlier is moved out of the way first and
+// only removed once the new one is in place.
+fn replace_dir(staging: TempDir, path: &Path) -> Result<(), String> {
+    let parent = parent_dir(path);
+    let old = tempfile::Builder::new()
+        .prefix(".old-instance")
+        .tempdir_in(parent)
+        .map_err(|e| e.to_string())?;
+    let old_path = old.path().join("instance.ufo");
+
+    let replacing = path.exists();
+    if replacing {
+        fs::rename(path, &old_path).map_err(|e| e.to_string())?;
+    }
+    if let Err(e) = fs::rename(staging.path(), path) {
+        if replacing {
+            let _ = fs::rename(&old_path, path);
+        }
+        return Err(e.to_string());
+    }
+    // now at `path`, so it mustn't be cleaned up
+    let _ = staging.into_path();
+    Ok(())
+}
+
+fn write_plist<T: serde::Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    plist::to_file_xml(path, value).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn blend(values: impl Iterator<Item = f64>, weights: &[f64]) -> f64 {
+    values.zip(weights).map(|(value, weight)| value * weight).sum()
+}
+
+/// The glyph `glifname` of `masters` blended by `weights`, shaped like `masters[base]`'s. Masters
+/// other than the base that weigh nothing are left out, so they may lack the glyph or not match
+/// up with the others. None if any other master lacks it or the masters' outlines don't match up.
+pub fn interpolate_glyph(
+    masters: &[UFO],
+    glifname: &str,
+    weights: &[f64],
+    base: usize,
+) -> Option<Glif<()>> {
+    let mut glifs: Vec<&Glif<()>> = Vec::new();
+    let mut present_weights = Vec::new();
+    let mut present_base = None;
+    for (i, (master, &weight)) in masters.iter().zip(weights).enumerate() {
+        if weight == 0. && i != base {
+            continue;
+        }
+        match master.get(glifname) {
+            Some(entry) if entry.is_broken() => return None,
+            Some(entry) => {
+                if i == base {
+                    present_base = Some(glifs.len());
+                }
+                glifs.push(&entry.glif);
+                present_weights.push(weight);
+            }
+            None => return None,
+        }
+    }
+
+    interpolate_glif(&glifs, &present_weights, present_base?)
+}
+
+fn compatible(glifs: &[&Glif<()>]) -> bool {
+    let empty = Vec::new();
+    let outlines: Vec<_> = glifs
+        .iter()
+        .map(|g| g.outline.as_ref().unwrap_or(&empty))
+        .collect();
+    let first = outlines[0];
+
+    // components aren't blended, so the masters have to at least agree on them
+    let components = component_signature(glifs[0]);
+    if glifs.iter().any(|g| component_signature(g) != components) {
+        return false;
+    }
+
+    outlines.iter().all(|outline| {
+        outline.len() == first.len()
+            && outline.iter().zip(first).all(|(contour, other)| {
+                contour.len() == other.len() && contour.is_open() == other.is_open()
+            })
+    })
+}
+
+fn handle_position(handle: Handle, x: f32, y: f32) -> (f32, f32) {
+    match handle {
+        Handle::At(hx, hy) => (hx, hy),
+        Handle::Colocated => (x, y),
+    }
+}
+
+/// Blends the outlines, advance width and anchors of `glifs` by `weights`. Everything else, and
+/// anchors not every master has, come from `glifs[base]`. None if the outlines or components
+/// don't match up.
+pub fn interpolate_glif(glifs: &[&Glif<()>], weights: &[f64], base: usize) -> Option<Glif<()>> {
+    if !compatible(glifs) {
+        return None;
+    }
+
+    let mut glif = glifs[base].clone();
+
+    if let Some(outline) = &mut glif.outline {
+        for (ci, contour) in outline.iter_mut().enumerate() {
+            for (pi, point) in contour.iter_mut().enumerate() {
+                let points: Vec<_> = glifs
+                    .iter()
+                    .map(|g| &g.outline.as_ref().unwrap()[ci][pi])
+                    .collect();
+
+                let x = blend(points.iter().map(|p| p.x as f64), weights) as f32;
+                let y = blend(points.iter().map(|p| p.y as f64), weights) as f32;
+
+                let handles = [
+                    (points.iter().map(|p| (p.a, p.x, p.y)).collect::<Vec<_>>(), &mut point.a),
+                    (points.iter().map(|p| (p.b, p.x, p.y)).collect::<Vec<_>>(), &mut point.b),
+                ];
+                for (masters, handle) in handles {
+                    if masters.iter().all(|(h, _, _)| *h == Handle::Colocated) {
+                        *handle = Handle::Colocated;
+                        continue;
+                    }
+                    let positions: Vec<_> = masters
+                        .into_iter()
+                        .map(|(h, px, py)| handle_position(h, px, py))
+                        .collect();
+                    *handle = Handle::At(
+                        blend(positions.iter().map(|p| p.0 as f64), weights) as f32,
+                        blend(positions.iter().map(|p| p.1 as f64), weights) as f32,
+                    );
+                }
+
+                point.x = x;
+                point.y = y;
+            }
+        }
+    }
+
+    if glifs.iter().all(|g| g.width.is_some()) {
+        let width = blend(glifs.iter().map(|g| g.width.unwrap() as f64), weights);
+        glif.width = Some(width.round().max(0.) as u64);
+    }
+
+    for anchor in &mut glif.anchors {
+        let matching: Option<Vec<_>> = glifs
+            .iter()
+            .map(|g| g.anchors.iter().find(|a| a.class == anchor.class))
+            .collect();
+        if let Some(matching) = matching {
+            anchor.x = blend(matching.iter().map(|a| a.x as f64), weights) as f32;
+            anchor.y = blend(matching.iter().map(|a| a.y as f64), weights) as f32;
+        }
+    }
+
+    Some(glif)
+}
+
+fn as_number(value: &Plist) -> Option<f64> {
+    match value {
+        Plist::Integer(i) => i.as_signed().map(|i| i as f64),
+        Plist::Real(r) => Some(*r),
+        _ => None,
+    }
+}
+
+// Integers stay integers, rounded.
+fn blended_like(original: &Plist, value: f64) -> Plist {
+    match original {
+        Plist::Integer(_) => Plist::Integer((value.round() as i64).into()),
+        _ => Plist::Real(value),
+    }
+}
+
+fn interpolate_value(values: &[Option<&Plist>], weights: &[f64], base: usize) -> Option<Plist> {
+    let original = values[base]?;
+    let values: Option<Vec<&Plist>> = values.iter().copied().collect();
+    let values = values?;
+
+    match original {
+        Plist::Integer(_) | Plist::Real(_) => {
+            let numbers: Option<Vec<f64>> = values.iter().map(|v| as_number(v)).collect();
+            Some(blended_like(original, blend(numbers?.into_iter(), weights)))
+        }
+        Plist::Array(items) => {
+            let arrays: Option<Vec<&Vec<Plist>>> =
+                values.iter().map(|v| v.as_array()).collect();
+            let arrays = arrays?;
+            if arrays.iter().any(|a| a.len() != items.len()) {
+                return None;
+            }
+            let blended = items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    let numbers: Option<Vec<f64>> =
+                        arrays.iter().map(|a| as_number(&a[i])).collect();
+                    Some(blended_like(item, blend(numbers?.into_iter(), weights)))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(Plist::Array(blended))
+        }
+        _ => None,
+    }
+}
+
+// Numbers and lists of numbers that every master has are blended; the rest comes from the
+// default master.
+fn interpolate_dict(raws: &[&Dictionary], weights: &[f64], base: usize) -> Dictionary {
+    let mut dict = raws[base].clone();
+
+    for (key, value) in dict.iter_mut() {
+        if NOT_INTERPOLATED.contains(&key.as_str()) {
+            continue;
+        }
+        let values: Vec<Option<&Plist>> = raws.iter().map(|raw| raw.get(key)).collect();
+        if let Some(blended) = interpolate_value(&values, weights, base) {
+            *value = blended;
+        }
+    }
+
+    dict
+}
+
+fn name_instance(fontinfo: &mut Dictionary, instance: &Instance) {
+    let names = [
+        ("familyName", &instance.familyname),
+        ("styleName", &instance.stylename),
+        ("postscriptFontName", &instance.postscriptfontname),
+    ];
+    for (key, name) in names {
+        if let Some(name) = name {
+            fontinfo.insert(key.to_string(), name.clone().into());
+        }
+    }
+
+    // the default master's full name would be wrong for every other instance
+    fontinfo.remove("postscriptFullName");
+    if let (Some(family), Some(style)) = (&instance.familyname, &instance.stylename) {
+        fontinfo.insert(
+            "postscriptFullName".to_string(),
+            format!("{} {}", family, style).into(),
+        );
+    }
+}
+
+fn read_kerning(ufo_path: &Path) -> Result<Dictionary, String> {
+    let path = ufo_path.join("kerning.plist");
+    if !path.is_file() {
+        return Ok(Dictionary::new());
+    }
+    plist::from_file(&path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// A pair a master doesn't kern counts as 0 in that master.
+fn interpolate_kerning(kernings: &[Dictionary], weights: &[f64]) -> Dictionary {
+    let mut pairs: BTreeMap<(String, String), f64> = BTreeMap::new();
+
+    for (kerning, weight) in kernings.iter().zip(weights) {
+        for (first, seconds) in kerning {
+            let seconds = match seconds.as_dictionary() {
+                Some(seconds) => seconds,
+                None => continue,
+            };
+            for (second, value) in seconds {
+                if let Some(value) = as_number(value) {
+                    *pairs.entry((first.clone(), second.clone())).or_default() += value * weight;
+                }
+            }
+        }
+    }
+
+    let mut kerning = Dictionary::new();
+    for ((first, second), value) in pairs {
+        let value = value.round() as i64;
+        if value == 0 {
+            continue;
+        }
+        if !kerning.contains_key(&first) {
+            kerning.insert(first.clone(), Plist::Dictionary(Dictionary::new()));
+        }
+        if let Some(seconds) = kerning.get_mut(&first).and_then(Plist::as_dictionary_mut) {
+            seconds.insert(second, Plist::Integer(value.into()));
+        }
+    }
+    kerning
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glifparser::{Anchor, Point, PointType};
+
+    fn glif(points: &[(f32, f32)], width: u64, anchors: &[(&str, f32, f32)]) -> Glif<()> {
+        let mut glif = Glif::new();
+        glif.outline = Some(vec![points
+            .iter()
+            .map(|&(x, y)| Point::from_x_y_type((x, y), PointType::Line))
+            .collect()]);
+        glif.width = Some(width);
+        glif.anchors = anchors
+            .iter()
+            .map(|&(class, x, y)| Anchor {
+                x,
+                y,
+                class: Some(class.to_string()),
+                ..Default::default()
+            })
+            .collect();
+        glif
+    }
+
+    #[test]
+    fn blends_outlines_widths_and_anchors() {
+        let light = glif(
+            &[(0., 0.), (100., 0.), (100., 700.)],
+            200,
+            &[("top", 50., 700.)],
+        );
+        let mut bold = glif(
+            &[(0., 0.), (300., 0.), (300., 700.)],
+            400,
+            &[("top", 150., 700.), ("bottom", 150., 0.)],
+        );
+        bold.outline.as_mut().unwrap()[0][1].a = Handle::At(340., 100.);
+
+        let blended = interpolate_glif(&[&light, &bold], &[0.75, 0.25], 0).unwrap();
+        let contour = &blended.outline.as_ref().unwrap()[0];
+        let coordinates: Vec<_> = contour.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(coordinates, [(0., 0.), (150., 0.), (150., 700.)]);
+        // a handle only one master has is blended with the other's on-curve point
+        assert_eq!(contour[1].a, Handle::At(160., 25.));
+        assert_eq!(contour[1].b, Handle::Colocated);
+        assert_eq!(blended.width, Some(250));
+        // only the base master's anchors, and only blended if every master has them
+        assert_eq!(blended.anchors.len(), 1);
+        assert_eq!((blended.anchors[0].x, blended.anchors[0].y), (75., 700.));
+    }
+
+    #[test]
+    fn incompatible_outlines_dont_blend() {
+        let light = glif(&[(0., 0.), (100., 0.), (100., 700.)], 200, &[]);
+        let bold = glif(&[(0., 0.), (300., 0.), (300., 700.), (0., 700.)], 400, &[]);
+        assert!(interpolate_glif(&[&light, &bold], &[0.5, 0.5], 0).is_none());
+    }
+
+    #[test]
+    fn fontinfo_blends_numbers_only() {
+        let dict = |entries: Vec<(&str, Plist)>| -> Dictionary {
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect()
+        };
+        let light = dict(vec![
+            ("ascender", Plist::Integer(700.into())),
+            ("italicAngle", Plist::Real(0.)),
+            ("versionMajor", Plist::Integer(1.into())),
+            ("openTypeOS2WinAscent", Plist::Integer(900.into())),
+            ("familyName", Plist::String("Light".to_string())),
+            (
+                "postscriptBlueValues",
+                Plist::Array(vec![Plist::Integer((-10).into()), Plist::Integer(0.into())]),
+            ),
+        ]);
+        let bold = dict(vec![
+            ("ascender", Plist::Integer(751.into())),
+            ("italicAngle", Plist::Real(-10.)),
+            ("versionMajor", Plist::Integer(2.into())),
+            ("familyName", Plist::String("Bold".to_string())),
+            (
+                "postscriptBlueValues",
+                Plist::Array(vec![Plist::Integer((-20).into()), Plist::Integer(0.into())]),
+            ),
+        ]);
+
+        let blended = interpolate_dict(&[&light, &bold], &[0.5, 0.5], 0);
+        // integers stay integers, rounded
+        assert_eq!(blended["ascender"], Plist::Integer(726.into()));
+        assert_eq!(blended["italicAngle"], Plist::Real(-5.));
+        assert_eq!(blended["versionMajor"], Plist::Integer(1.into()));
+        // not every master has it
+        assert_eq!(blended["openTypeOS2WinAscent"], Plist::Integer(900.into()));
+        assert_eq!(blended["familyName"], Plist::String("Light".to_string()));
+        assert_eq!(
+            blended["postscriptBlueValues"],
+            Plist::Array(vec![Plist::Integer((-15).into()), Plist::Integer(0.into())])
+        );
+    }
+
+    #[test]
+    fn kerning_missing_from_a_master_counts_as_zero() {
+        let kerning = |pairs: &[(&str, &str, i64)]| -> Dictionary {
+            let mut kerning = Dictionary::new();
+            for &(first, second, value) in pairs {
+                let mut seconds = Dictionary::new();
+                seconds.insert(second.to_string(), Plist::Integer(value.into()));
+                kerning.insert(first.to_string(), Plist::Dictionary(seconds));
+            }
+            kerning
+        };
+        let light = kerning(&[("T", "o", -40), ("A", "V", -10)]);
+        let bold = kerning(&[("T", "o", -80), ("L", "T", -60)]);
+
+        let blended = interpolate_kerning(&[light, bold], &[0.75, 0.25]);
+        assert_eq!(
+            blended,
+            kerning(&[("T", "o", -50), ("A", "V", -8), ("L", "T", -15)])
+        );
+
+        // pairs that blend to nothing are dropped
+        let cancelled = interpolate_kerning(
+            &[kerning(&[("A", "V", -10)]), kerning(&[("A", "V", 10)])],
+            &[0.5, 0.5],
+        );
+        assert!(cancelled.is_empty());
+    }
+}
@@ -1,208 +1,386 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashSet};
 
-use glifparser::contour::State;
+use glifparser::{contour::State, Glif, Handle, Point, PointType};
 
-use crate::viewer::UFO;
+use crate::{parsing::glyph_entries::GlyphEntry, viewer::UFO};
+
+pub mod correspondence;
+pub mod instance;
+pub mod model;
+pub mod sync;
+
+use correspondence::ContourMatch;
+
+// Contour order, direction and start point are guessed by `correspondence::suggest`, so they're
+// reported but don't make the check fail.
+const HEURISTIC_SETS: usize = 3;
 
 fn create_glyph_set(masters: &Vec<UFO>) -> HashSet<String> {
     let mut glyph_set = HashSet::new();
 
     for ufo in masters {
         for entry in &ufo.glyph_entries {
-            glyph_set.insert(entry.uniname.clone());
+            glyph_set.insert(entry.glifname.clone());
         }
     }
 
-    return glyph_set;
-}
-
-// returns the names of missing glyphs in a given master as compared to the glyph set
-// created by the glyphs of all masters
-fn get_master_glyph_set_difference(master: &UFO, glyph_set: &HashSet<String>) -> HashSet<String> {
-    let master_set: HashSet<String> = master.glyph_entries
-        .iter()
-        .map(|e| e.uniname.clone())
-        .collect();
-
-    let difference: HashSet<_> = glyph_set.difference(&master_set).cloned().collect();
-
-    difference
+    glyph_set
 }
 
+/// Glyphs are identified by glifname throughout. Every glyph is checked on its own, so that one
+/// can be checked again without redoing the rest.
+#[derive(Default)]
 pub struct InterpolationCheckResults {
     pub succeeded: bool,
     pub glyph_set: HashSet<String>,
+    pub missing_glyphs: HashSet<String>,
+    // the glyphs each master lacks, in `UFOViewer::masters` order
+    pub missing_by_master: Vec<HashSet<String>>,
     pub contour_count_collisions: HashSet<String>,
     pub point_count_collisions: HashSet<String>,
     pub contour_open_collisions: HashSet<String>,
+    pub point_type_collisions: HashSet<String>,
+    pub segment_collisions: HashSet<String>,
+    pub component_collisions: HashSet<String>,
+    pub anchor_collisions: HashSet<String>,
+    // `correspondence::suggest`'s guesses; these only make for warnings
+    pub contour_order_collisions: HashSet<String>,
+    pub direction_collisions: HashSet<String>,
+    pub start_point_collisions: HashSet<String>,
+    // glyphs that won't interpolate
     pub combined: HashSet<String>,
+    // glyphs that will, but whose contours seem reordered, reversed or restarted
+    pub warnings: HashSet<String>,
+    // what exactly is wrong with each glyph in `combined` or `warnings`
+    pub reports: BTreeMap<String, GlyphReport>,
+}
+
+/// How one incompatible glyph differs between the masters. Per-master values are in
+/// `UFOViewer::masters` order, and None where the master lacks the glyph.
+#[derive(Debug, Clone, Default)]
+pub struct GlyphReport {
+    pub glifname: String,
+    pub filenames: Vec<Option<String>>,
+    // masters allowed to lack the glyph, see `UFO::is_sparse`
+    pub sparse: Vec<bool>,
+    pub contour_counts: Vec<Option<usize>>,
+    // by contour index, only for the contours the masters disagree on
+    pub point_counts: BTreeMap<usize, Vec<Option<usize>>>,
+    pub open_states: BTreeMap<usize, Vec<Option<bool>>>,
+    // as `point_type_signature` and `segment_signature`, only for contours whose point counts match
+    pub point_types: BTreeMap<usize, Vec<Option<String>>>,
+    pub segments: BTreeMap<usize, Vec<Option<String>>>,
+    // as `component_signature` and `anchor_signature`, empty when the masters agree
+    pub components: Vec<Option<String>>,
+    pub anchors: Vec<Option<String>>,
+    // the first master that has the glyph, and for each master whose contours look reordered,
+    // reversed or restarted compared to it, which of its contours matches each reference contour
+    pub reference: usize,
+    pub correspondences: BTreeMap<usize, Vec<ContourMatch>>,
+}
+
+impl GlyphReport {
+    /// Indices of the masters that lack the glyph, other than sparse ones.
+    pub fn missing_in(&self) -> Vec<usize> {
+        self.filenames
+            .iter()
+            .enumerate()
+            .filter(|(i, filename)| {
+                filename.is_none() && !self.sparse.get(*i).copied().unwrap_or(false)
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
 }
 
 pub(crate) fn check_interpolatable(masters: &Vec<UFO>) -> InterpolationCheckResults {
-    // create the set of all glyphs from all masters
-    let glyph_set = create_glyph_set(masters);
+    let mut results = InterpolationCheckResults {
+        succeeded: true,
+        missing_by_master: vec![HashSet::new(); masters.len()],
+        ..Default::default()
+    };
 
-    // get the missing glyphs from each master
-    let glyph_set_differences: Vec<HashSet<String>> = masters
-        .iter()
-        .map(|m| get_master_glyph_set_difference(m, &glyph_set))
-        .collect();
+    let sparse: Vec<bool> = masters.iter().map(UFO::is_sparse).collect();
 
-    // Combine all sets into one
-    let combined_differences: HashSet<String> = glyph_set_differences
-        .into_iter()
-        .flatten()
-        .collect();
+    for name in create_glyph_set(masters) {
+        let entries: Vec<Option<&GlyphEntry>> = masters.iter().map(|m| m.get(&name)).collect();
+        results.record(check_glyph(&name, &entries, &sparse));
+    }
 
-    let contour_counts = count_different_contour_counts(masters);
-    let contour_counts_set: HashSet<String> = contour_counts.into_iter().map(|(s, _)| s).collect();
+    results
+}
 
-    let point_counts = count_different_point_counts(masters);
-    let point_counts_set: HashSet<String> = point_counts.into_iter().map(|(s, _)| s).collect();
+impl InterpolationCheckResults {
+    /// Checks glyph `glifname` again, e.g. after one of its masters was saved, leaving every
+    /// other glyph's results alone.
+    pub fn recheck_glyph(&mut self, masters: &[UFO], glifname: &str) {
+        self.forget(glifname);
 
-    let open_states_counts = count_different_contour_open_state(masters);
-    let open_states_counts_set: HashSet<String> = open_states_counts.into_iter().map(|(s, _)| s).collect();
+        let entries: Vec<Option<&GlyphEntry>> = masters.iter().map(|m| m.get(glifname)).collect();
+        if entries.iter().any(Option::is_some) {
+            let sparse: Vec<bool> = masters.iter().map(UFO::is_sparse).collect();
+            self.record(check_glyph(glifname, &entries, &sparse));
+        }
 
-    let mut success = false;
-    if combined_differences.is_empty() && contour_counts_set.is_empty() && point_counts_set.is_empty() && open_states_counts_set.is_empty() {
-        success = true;
+        self.succeeded = self.combined.is_empty();
     }
 
-    let combined_sets: HashSet<String> = combined_differences.union(&point_counts_set).cloned().collect();
-    let combined_sets: HashSet<String> = combined_sets.union(&contour_counts_set).cloned().collect();
-    let combined_sets: HashSet<String> = combined_sets.union(&open_states_counts_set).cloned().collect();
-
-    return InterpolationCheckResults {
-        succeeded: success,
-        glyph_set,
-        contour_count_collisions: contour_counts_set,
-        point_count_collisions: point_counts_set,
-        contour_open_collisions: open_states_counts_set,
-        combined: combined_sets
+    // One set per kind of problem, in the order `record` flags them. The last `HEURISTIC_SETS`
+    // are warnings.
+    fn problem_sets_mut(&mut self) -> [&mut HashSet<String>; 11] {
+        [
+            &mut self.missing_glyphs,
+            &mut self.contour_count_collisions,
+            &mut self.point_count_collisions,
+            &mut self.contour_open_collisions,
+            &mut self.point_type_collisions,
+            &mut self.segment_collisions,
+            &mut self.component_collisions,
+            &mut self.anchor_collisions,
+            &mut self.contour_order_collisions,
+            &mut self.direction_collisions,
+            &mut self.start_point_collisions,
+        ]
     }
-}
 
-fn count_different_contour_counts(masters: &Vec<UFO>) -> HashMap<String, usize> {
-    let dummy = Vec::new();
-    // for each master grab a contour count
-    let contour_count_set: Vec<HashMap<String, usize>> = masters
-        .iter()
-        .map(|v| {
-            v.glyph_entries
-                .iter()
-                .map(|e| {
-                    (e.uniname.clone(), e.glif.outline.as_ref().unwrap_or(&dummy).len())
-                })
-                .collect()
-        })
-        .collect();
-    
-    let mut counts: HashMap<String, HashSet<usize>> = HashMap::new();
-
-    for count_set in contour_count_set {
-        for (idx, count) in count_set {
-            counts.entry(idx)
-                .or_insert_with(HashSet::new)
-                .insert(count);
+    fn forget(&mut self, glifname: &str) {
+        self.glyph_set.remove(glifname);
+        for set in self.problem_sets_mut() {
+            set.remove(glifname);
         }
+        for missing in &mut self.missing_by_master {
+            missing.remove(glifname);
+        }
+        self.combined.remove(glifname);
+        self.warnings.remove(glifname);
+        self.reports.remove(glifname);
     }
 
-    let filtered_counts: HashMap<String, usize> = counts.into_iter()
-        .filter_map(|(idx, set)| {
-            if set.len() >= 2 {
-                Some((idx, set.len()))
-            } else {
-                None
+    fn record(&mut self, report: GlyphReport) {
+        let name = report.glifname.clone();
+        self.glyph_set.insert(name.clone());
+
+        let missing = report.missing_in();
+        if self.missing_by_master.len() < report.filenames.len() {
+            self.missing_by_master.resize(report.filenames.len(), HashSet::new());
+        }
+        for &mi in &missing {
+            self.missing_by_master[mi].insert(name.clone());
+        }
+
+        let present: Vec<bool> = report.filenames.iter().map(Option::is_some).collect();
+        let contours_match = |problem: fn(usize, &ContourMatch) -> bool| {
+            report
+                .correspondences
+                .values()
+                .any(|matches| matches.iter().enumerate().any(|(ci, m)| problem(ci, m)))
+        };
+        let flags = [
+            !missing.is_empty(),
+            disagree(&report.contour_counts, &present),
+            !report.point_counts.is_empty(),
+            !report.open_states.is_empty(),
+            !report.point_types.is_empty(),
+            !report.segments.is_empty(),
+            !report.components.is_empty(),
+            !report.anchors.is_empty(),
+            contours_match(|ci, m| m.contour != ci),
+            contours_match(|_, m| m.reversed),
+            contours_match(|_, m| m.start != 0),
+        ];
+
+        let (mut failed, mut warned) = (false, false);
+        let heuristic_from = flags.len() - HEURISTIC_SETS;
+        for (i, (set, flagged)) in self.problem_sets_mut().into_iter().zip(flags).enumerate() {
+            if flagged {
+                set.insert(name.clone());
+                if i < heuristic_from {
+                    failed = true;
+                } else {
+                    warned = true;
+                }
             }
-        })
-        .collect();
+        }
+
+        if failed {
+            self.combined.insert(name.clone());
+            self.succeeded = false;
+        } else if warned {
+            self.warnings.insert(name.clone());
+        }
+        if failed || warned {
+            self.reports.insert(name, report);
+        }
+    }
+}
 
-    filtered_counts
+// Masters lacking the glyph altogether don't count as disagreeing.
+fn disagree<T: PartialEq>(values: &[Option<T>], present: &[bool]) -> bool {
+    let mut present_values = values.iter().zip(present).filter(|(_, p)| **p).map(|(v, _)| v);
+    match present_values.next() {
+        Some(first) => present_values.any(|v| v != first),
+        None => false,
+    }
 }
 
-// Returns a HashMap of name and index into outline
-fn count_different_point_counts(masters: &Vec<UFO>) -> HashMap<String, HashMap<usize, HashSet<usize>>> {
-    let point_count_set: Vec<HashMap<String, HashMap<usize, usize>>> = masters
+// Compares glyph `name` across the masters; `entries` has each master's, if it has one. Masters
+// flagged in `sparse` may lack it, but what they do have is compared all the same.
+fn check_glyph(name: &str, entries: &[Option<&GlyphEntry>], sparse: &[bool]) -> GlyphReport {
+    let empty = Vec::new();
+    let outlines: Vec<_> = entries
         .iter()
-        .map(|ufo| {
-            ufo.glyph_entries
-                .iter()
-                .map(|ge| {
-                    let point_counts: HashMap<usize, usize> = ge.glif.outline.clone()
-                        .unwrap_or(Vec::new())
-                        .iter()
-                        .enumerate()
-                        .map(|(i, contour)| (i, contour.len()))
-                        .collect();
-
-                    (ge.uniname.clone(), point_counts)
-                })
-                .collect()
-        })
+        .map(|e| e.map(|e| e.glif.outline.as_ref().unwrap_or(&empty)))
         .collect();
 
-    let mut final_counts: HashMap<String, HashMap<usize, HashSet<usize>>> = HashMap::new();
+    let contour_counts: Vec<Option<usize>> = outlines.iter().map(|o| o.map(Vec::len)).collect();
+    let most_contours = contour_counts.iter().flatten().copied().max().unwrap_or(0);
+
+    let present: Vec<bool> = entries.iter().map(Option::is_some).collect();
+
+    let mut point_counts = BTreeMap::new();
+    let mut open_states = BTreeMap::new();
+    let mut point_types = BTreeMap::new();
+    let mut segments = BTreeMap::new();
+    for ci in 0..most_contours {
+        // masters with fewer contours are already told apart by their contour count
+        let has_contour: Vec<bool> = outlines
+            .iter()
+            .map(|o| o.is_some_and(|o| ci < o.len()))
+            .collect();
+
+        let counts: Vec<Option<usize>> = outlines
+            .iter()
+            .map(|o| o.and_then(|o| o.get(ci)).map(|c| c.len()))
+            .collect();
+        if disagree(&counts, &has_contour) {
+            point_counts.insert(ci, counts);
+        }
 
-    for count_set in point_count_set {
-        for (glyph_name, count) in count_set {
-            let glyph_count = final_counts.entry(glyph_name.clone()).or_insert(HashMap::new());
+        let states: Vec<Option<bool>> = outlines
+            .iter()
+            .map(|o| o.and_then(|o| o.get(ci)).map(|c| c.is_open()))
+            .collect();
+        if disagree(&states, &has_contour) {
+            open_states.insert(ci, states);
+        }
 
-            for (i, point_len) in count.iter() {
-                glyph_count
-                    .entry(*i)
-                    .or_insert(HashSet::new())
-                    .insert(*point_len);
-            }
+        // with different point counts the signatures can't line up anyway
+        if point_counts.contains_key(&ci) {
+            continue;
+        }
+        let types: Vec<Option<String>> = outlines
+            .iter()
+            .map(|o| o.and_then(|o| o.get(ci)).map(|c| point_type_signature(c)))
+            .collect();
+        if disagree(&types, &has_contour) {
+            point_types.insert(ci, types);
         }
-    }
 
-    for (_, contour_map) in &mut final_counts {
-        contour_map.retain(|_, v| v.len() > 1)
+        let structure: Vec<Option<String>> = outlines
+            .iter()
+            .map(|o| o.and_then(|o| o.get(ci)).map(|c| segment_signature(c)))
+            .collect();
+        if disagree(&structure, &has_contour) {
+            segments.insert(ci, structure);
+        }
     }
 
-    final_counts.retain(|_, v| v.len() > 0);
-    final_counts
-}
-
-fn count_different_contour_open_state(masters: &Vec<UFO>) -> HashMap<String, HashMap<usize, HashSet<bool>>> {
-    let point_count_set: Vec<HashMap<String, HashMap<usize, bool>>> = masters
+    let mut components: Vec<Option<String>> = entries
         .iter()
-        .map(|ufo| {
-            ufo.glyph_entries
-                .iter()
-                .map(|ge| {
-                    let point_counts: HashMap<usize, bool> = ge.glif.outline.clone()
-                        .unwrap_or(Vec::new())
-                        .iter()
-                        .enumerate()
-                        .map(|(i, contour)| (i, contour.is_open()))
-                        .collect();
-
-                    (ge.uniname.clone(), point_counts)
-                })
-                .collect()
-        })
+        .map(|e| e.map(|e| component_signature(&e.glif)))
+        .collect();
+    if !disagree(&components, &present) {
+        components.clear();
+    }
+    let mut anchors: Vec<Option<String>> = entries
+        .iter()
+        .map(|e| e.map(|e| anchor_signature(&e.glif)))
         .collect();
+    if !disagree(&anchors, &present) {
+        anchors.clear();
+    }
 
-    let mut final_counts: HashMap<String, HashMap<usize, HashSet<bool>>> = HashMap::new();
+    // compare every master's contours to those of the first master having the glyph
+    let reference = present.iter().position(|p| *p).unwrap_or(0);
+    let correspondences = match entries.get(reference).copied().flatten() {
+        Some(reference_entry) => entries
+            .iter()
+            .enumerate()
+            .skip(reference + 1)
+            .filter_map(|(mi, entry)| {
+                let matches = correspondence::suggest(&reference_entry.glif, &entry.as_ref()?.glif)?;
+                let identity = matches.iter().enumerate().all(|(ci, m)| m.is_identity(ci));
+                (!identity).then_some((mi, matches))
+            })
+            .collect(),
+        None => BTreeMap::new(),
+    };
+
+    GlyphReport {
+        reference,
+        correspondences,
+        glifname: name.to_string(),
+        filenames: entries.iter().map(|e| e.map(|e| e.filename.clone())).collect(),
+        sparse: sparse.to_vec(),
+        contour_counts,
+        point_counts,
+        open_states,
+        point_types,
+        segments,
+        components,
+        anchors,
+    }
+}
 
-    for count_set in point_count_set {
-        for (glyph_name, count) in count_set {
-            let glyph_count = final_counts.entry(glyph_name.clone()).or_insert(HashMap::new());
+/// The base glyphs of a glyph's components, in order. Components are interpolated pairwise, so
+/// the masters need the same ones in the same order.
+pub fn component_signature(glif: &Glif<()>) -> String {
+    glif.components
+        .vec
+        .iter()
+        .map(|c| c.base.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
-            for (i, point_len) in count.iter() {
-                glyph_count
-                    .entry(*i)
-                    .or_insert(HashSet::new())
-                    .insert(*point_len);
-            }
-        }
-    }
+/// The names of a glyph's anchors, sorted, since anchors are matched up by name rather than
+/// position.
+pub fn anchor_signature(glif: &Glif<()>) -> String {
+    let mut names: Vec<&str> = glif
+        .anchors
+        .iter()
+        .map(|a| a.class.as_deref().unwrap_or("(unnamed)"))
+        .collect();
+    names.sort_unstable();
+    names.join(" ")
+}
 
-    for (_, contour_map) in &mut final_counts {
-        contour_map.retain(|_, v| v.len() > 1)
-    }
+/// One letter per point: M(ove), L(ine), C(urve), Q(uadratic curve) or O(ff-curve).
+pub fn point_type_signature(contour: &[Point<()>]) -> String {
+    contour
+        .iter()
+        .map(|p| match p.ptype {
+            PointType::Move => 'M',
+            PointType::Line => 'L',
+            PointType::Curve => 'C',
+            PointType::QCurve => 'Q',
+            PointType::OffCurve => 'O',
+            _ => '?',
+        })
+        .collect()
+}
 
-    final_counts.retain(|_, v| v.len() > 0);
-    final_counts
+/// Which off-curve handles each point has, one character per point: `.` for none, `<` for only
+/// the handle towards the previous point, `>` for only the one towards the next point and `=`
+/// for both. Masters with the same point types can still place their handles differently.
+pub fn segment_signature(contour: &[Point<()>]) -> String {
+    contour
+        .iter()
+        .map(|p| match (p.b != Handle::Colocated, p.a != Handle::Colocated) {
+            (false, false) => '.',
+            (true, false) => '<',
+            (false, true) => '>',
+            (true, true) => '=',
+        })
+        .collect()
 }
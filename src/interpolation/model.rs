@@ -0,0 +1,485 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use crate::designspace::{Axis, Location};
+
+/// A location with every axis normalized to -1..1 around the axis' default. Axes at their default
+/// are left out, so the default location is empty.
+pub type NormalizedLocation = BTreeMap<String, f64>;
+
+// axis name → (lower, peak, upper), all normalized
+type Region = BTreeMap<String, (f64, f64, f64)>;
+
+/// Checks that the default of every axis lies between its minimum and maximum, which the
+/// Designspace editor doesn't enforce while they're being typed in.
+pub fn check_axes(axes: &[Axis]) -> Result<(), String> {
+    for axis in axes {
+        let (minimum, default, maximum) =
+            (axis.design_minimum(), axis.design_default(), axis.design_maximum());
+        if !(minimum <= default && default <= maximum) {
+            return Err(format!(
+                "the default of axis {} doesn't lie between its minimum and maximum",
+                axis.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Values beyond an axis' minimum or maximum are clamped to it. Axes that `check_axes` rejects
+/// don't panic, but normalize to nothing useful.
+pub fn normalize(axes: &[Axis], location: &Location) -> NormalizedLocation {
+    let mut normalized = NormalizedLocation::new();
+
+    for axis in axes {
+        let (minimum, default, maximum) =
+            (axis.design_minimum(), axis.design_default(), axis.design_maximum());
+        let value = location.get(&axis.name).copied().unwrap_or(default);
+
+        let v = if value < default && minimum < default {
+            (value.max(minimum) - default) / (default - minimum)
+        } else if value > default && default < maximum {
+            (value.min(maximum) - default) / (maximum - default)
+        } else {
+            0.
+        };
+        if v != 0. {
+            normalized.insert(axis.name.clone(), v);
+        }
+    }
+
+    normalized
+}
+
+/// The same model fontTools uses to interpolate between any number of masters placed anywhere
+/// in the designspace, as long as one of them sits at the default location. Each master
+/// contributes a delta over the masters before it, scaled by how far the wanted location is into
+/// the region it supports.
+#[derive(Debug, Clone)]
+pub struct VariationModel {
+    // master indices, in the order their deltas are computed
+    order: Vec<usize>,
+    supports: Vec<Region>,
+    // for each master (in `order`), how much of every earlier master's delta it contains
+    delta_weights: Vec<Vec<(usize, f64)>>,
+}
+
+impl VariationModel {
+    pub fn new(locations: &[NormalizedLocation], axis_order: &[String]) -> Result<Self, String> {
+        match locations.iter().filter(|l| l.is_empty()).count() {
+            0 => return Err("no master is at the default location".to_string()),
+            1 => {}
+            _ => return Err("more than one master is at the default location".to_string()),
+        }
+        for (i, location) in locations.iter().enumerate() {
+            if locations[..i].contains(location) {
+                return Err("two masters are at the same location".to_string());
+            }
+        }
+
+        let key = sort_key(locations, axis_order);
+        let mut order: Vec<usize> = (0..locations.len()).collect();
+        order.sort_by(|&a, &b| compare_keys(&key(&locations[a]), &key(&locations[b])));
+
+        let sorted: Vec<&NormalizedLocation> = order.iter().map(|&i| &locations[i]).collect();
+        let supports = master_supports(&sorted);
+        let delta_weights = sorted
+            .iter()
+            .enumerate()
+            .map(|(i, location)| {
+                supports[..i]
+                    .iter()
+                    .enumerate()
+                    .map(|(j, support)| (j, support_scalar(location, support)))
+                    .filter(|&(_, scalar)| scalar != 0.)
+                    .collect()
+            })
+            .collect();
+
+        Ok(VariationModel {
+            order,
+            supports,
+            delta_weights,
+        })
+    }
+
+    /// The index of the master at the default location.
+    pub fn default_master(&self) -> usize {
+        self.order[0]
+    }
+
+    /// How much each master contributes at `location`, by master index. Any value interpolated
+    /// between the masters is the sum of the masters' values times these weights.
+    pub fn master_weights(&self, location: &NormalizedLocation) -> Vec<f64> {
+        let n = self.order.len();
+
+        // express each delta as a combination of master values
+        let mut deltas: Vec<Vec<f64>> = Vec::with_capacity(n);
+        for (i, weights) in self.delta_weights.iter().enumerate() {
+            let mut delta = vec![0.; n];
+            delta[self.order[i]] = 1.;
+            for &(j, weight) in weights {
+                for (d, earlier) in delta.iter_mut().zip(&deltas[j]) {
+                    *d -= earlier * weight;
+                }
+            }
+            deltas.push(delta);
+        }
+
+        let mut weights = vec![0.; n];
+        for (support, delta) in self.supports.iter().zip(&deltas) {
+            let scalar = support_scalar(location, support);
+            if scalar == 0. {
+                continue;
+            }
+            for (w, d) in weights.iter_mut().zip(delta) {
+                *w += d * scalar;
+            }
+        }
+        weights
+    }
+}
+
+// Masters on fewer axes come first, so that the default master leads and masters off the axes
+// only add what the on-axis masters can't produce.
+fn sort_key<'a>(
+    locations: &[NormalizedLocation],
+    axis_order: &'a [String],
+) -> impl Fn(&NormalizedLocation) -> Vec<f64> + 'a {
+    // values of masters that lie on a single axis, per axis
+    let mut axis_points: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for location in locations.iter().filter(|l| l.len() == 1) {
+        let (axis, value) = location.iter().next().unwrap();
+        axis_points.entry(axis.clone()).or_insert_with(|| vec![0.]).push(*value);
+    }
+
+    move |location| {
+        let on_point = location
+            .iter()
+            .filter(|(axis, value)| axis_points.get(*axis).is_some_and(|p| p.contains(*value)))
+            .count();
+
+        let mut axes: Vec<&String> = location.keys().collect();
+        let index = |axis: &String| {
+            axis_order.iter().position(|a| a == axis).unwrap_or(0x10000) as f64
+        };
+        axes.sort_by(|a, b| index(a).total_cmp(&index(b)).then(a.cmp(b)));
+
+        let mut key = vec![location.len() as f64, -(on_point as f64)];
+        key.extend(axes.iter().map(|a| index(a)));
+        key.extend(axes.iter().map(|a| location[*a].signum()));
+        key.extend(axes.iter().map(|a| location[*a].abs()));
+        key
+    }
+}
+
+fn compare_keys(a: &[f64], b: &[f64]) -> Ordering {
+    for (x, y) in a.iter().zip(b) {
+        match x.total_cmp(y) {
+            Ordering::Equal => continue,
+            other => return other,
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+fn master_supports(locations: &[&NormalizedLocation]) -> Vec<Region> {
+    let mut minimums: BTreeMap<&String, f64> = BTreeMap::new();
+    let mut maximums: BTreeMap<&String, f64> = BTreeMap::new();
+    for location in locations {
+        for (axis, &value) in location.iter() {
+            let minimum = minimums.entry(axis).or_insert(value);
+            *minimum = minimum.min(value);
+            let maximum = maximums.entry(axis).or_insert(value);
+            *maximum = maximum.max(value);
+        }
+    }
+
+    let regions: Vec<Region> = locations
+        .iter()
+        .map(|location| {
+            location
+                .iter()
+                .map(|(axis, &peak)| {
+                    let triple = if peak > 0. {
+                        (0., peak, maximums[axis])
+                    } else {
+                        (minimums[axis], peak, 0.)
+                    };
+                    (axis.clone(), triple)
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut supports = Vec::with_capacity(regions.len());
+    for (i, region) in regions.iter().enumerate() {
+        let mut region = region.clone();
+
+        for previous in &regions[..i] {
+            // masters on other axes don't take part
+            if !previous.keys().eq(region.keys()) {
+                continue;
+            }
+            // nor do masters outside of this one's box
+            let relevant = region.iter().all(|(axis, &(lower, peak, upper))| {
+                let value = previous[axis].1;
+                value == peak || (lower < value && value < upper)
+            });
+            if !relevant {
+                continue;
+            }
+
+            // shrink the box away from the earlier master, along the axes where it lies
+            // proportionally furthest
+            let mut best_axes: Vec<(String, (f64, f64, f64))> = Vec::new();
+            let mut best_ratio = -1.;
+            for (axis, &(_, value, _)) in previous {
+                let (lower, peak, upper) = region[axis];
+                let (triple, ratio) = if value < peak {
+                    ((value, peak, upper), (value - peak) / (lower - peak))
+                } else if peak < value {
+                    ((lower, peak, value), (value - peak) / (upper - peak))
+                } else {
+                    continue;
+                };
+
+                if ratio > best_ratio {
+                    best_axes.clear();
+                    best_ratio = ratio;
+                }
+                if ratio == best_ratio {
+                    best_axes.push((axis.clone(), triple));
+                }
+            }
+
+            for (axis, triple) in best_axes {
+                region.insert(axis, triple);
+            }
+        }
+
+        supports.push(region);
+    }
+
+    supports
+}
+
+// How much of a master's delta applies at `location`: 1 at the master's own location, falling
+// linearly to 0 at the edges of its region.
+fn support_scalar(location: &NormalizedLocation, support: &Region) -> f64 {
+    let mut scalar = 1.;
+
+    for (axis, &(lower, peak, upper)) in support {
+        if peak == 0. || lower > peak || peak > upper || (lower < 0. && upper > 0.) {
+            continue;
+        }
+
+        let v = location.get(axis).copied().unwrap_or(0.);
+        if v == peak {
+            continue;
+        }
+        if v <= lower || upper <= v {
+            return 0.;
+        }
+        if v < peak {
+            scalar *= (v - lower) / (peak - lower);
+        } else {
+            scalar *= (v - upper) / (peak - upper);
+        }
+    }
+
+    scalar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weight_axis(minimum: f64, default: f64, maximum: f64) -> Axis {
+        Axis {
+            name: "Weight".to_string(),
+            tag: "wght".to_string(),
+            minimum,
+            default,
+            maximum,
+            ..Default::default()
+        }
+    }
+
+    fn at_weight(weight: f64) -> Location {
+        Location::from([("Weight".to_string(), weight)])
+    }
+
+    #[test]
+    fn normalize_clamps_to_axis() {
+        let axes = [weight_axis(400., 400., 900.)];
+        assert_eq!(
+            normalize(&axes, &at_weight(100.)),
+            NormalizedLocation::new()
+        );
+        assert_eq!(normalize(&axes, &at_weight(650.))["Weight"], 0.5);
+        assert_eq!(normalize(&axes, &at_weight(1000.))["Weight"], 1.);
+    }
+
+    #[test]
+    fn default_outside_of_axis() {
+        assert!(check_axes(&[weight_axis(100., 400., 900.)]).is_ok());
+        assert!(check_axes(&[weight_axis(100., 100., 100.)]).is_ok());
+
+        for axis in [
+            weight_axis(900., 400., 100.),
+            weight_axis(100., 950., 900.),
+            weight_axis(500., 400., 900.),
+        ] {
+            let axes = [axis];
+            assert!(check_axes(&axes).is_err());
+            for weight in [0., 100., 400., 500., 900., 1000.] {
+                let normalized = normalize(&axes, &at_weight(weight));
+                assert!(
+                    normalized.values().all(|v| v.is_finite()),
+                    "{:?}",
+                    normalized
+                );
+            }
+        }
+    }
+
+    fn location(values: &[(&str, f64)]) -> NormalizedLocation {
+        values
+            .iter()
+            .map(|&(axis, v)| (axis.to_string(), v))
+            .collect()
+    }
+
+    fn assert_weights(weights: &[f64], expected: &[f64]) {
+        assert_eq!(weights.len(), expected.len());
+        for (w, e) in weights.iter().zip(expected) {
+            assert!((w - e).abs() < 1e-9, "{:?} != {:?}", weights, expected);
+        }
+    }
+
+    #[test]
+    fn support_scalars_match_fonttools() {
+        let region = |values: &[(&str, (f64, f64, f64))]| -> Region {
+            values
+                .iter()
+                .map(|&(axis, t)| (axis.to_string(), t))
+                .collect()
+        };
+
+        assert_eq!(support_scalar(&location(&[]), &region(&[])), 1.);
+        assert_eq!(
+            support_scalar(&location(&[("wght", 0.2)]), &region(&[])),
+            1.
+        );
+        let scalar = support_scalar(
+            &location(&[("wght", 0.2)]),
+            &region(&[("wght", (0., 2., 3.))]),
+        );
+        assert!((scalar - 0.1).abs() < 1e-9);
+        let wght = region(&[("wght", (0., 2., 4.))]);
+        assert_eq!(support_scalar(&location(&[("wght", 2.5)]), &wght), 0.75);
+        assert_eq!(support_scalar(&location(&[("wght", 4.)]), &wght), 0.);
+        assert_eq!(support_scalar(&location(&[("wght", -1.)]), &wght), 0.);
+        // regions spanning the default don't scale
+        let both = region(&[("wght", (0., 2., 4.)), ("wdth", (-1., 0., 1.))]);
+        assert_eq!(
+            support_scalar(&location(&[("wght", 2.5), ("wdth", 0.5)]), &both),
+            0.75
+        );
+    }
+
+    #[test]
+    fn sparse_masters_blend_like_fonttools() {
+        // no master in the bold wide corner: it's reached by adding both deltas to the default
+        let locations = [
+            location(&[]),
+            location(&[("wght", 1.)]),
+            location(&[("wdth", 1.)]),
+        ];
+        let model = VariationModel::new(&locations, &[]).unwrap();
+        assert_eq!(model.default_master(), 0);
+        assert_weights(
+            &model.master_weights(&location(&[("wght", 0.5), ("wdth", 0.5)])),
+            &[0., 0.5, 0.5],
+        );
+        assert_weights(
+            &model.master_weights(&location(&[("wght", 1.), ("wdth", 1.)])),
+            &[-1., 1., 1.],
+        );
+
+        // an intermediate master splits the axis in two
+        let locations = [
+            location(&[("wght", 1.)]),
+            location(&[]),
+            location(&[("wght", 0.5)]),
+        ];
+        let model = VariationModel::new(&locations, &[]).unwrap();
+        assert_eq!(model.default_master(), 1);
+        assert_weights(
+            &model.master_weights(&location(&[("wght", 0.25)])),
+            &[0., 0.5, 0.5],
+        );
+        assert_weights(
+            &model.master_weights(&location(&[("wght", 0.75)])),
+            &[0.5, 0., 0.5],
+        );
+        assert_weights(
+            &model.master_weights(&location(&[("wdth", 1.)])),
+            &[0., 1., 0.],
+        );
+    }
+
+    #[test]
+    fn delta_weights_match_fonttools() {
+        // the example from fontTools.varLib.models.VariationModel
+        let locations = [
+            location(&[("wght", 100.)]),
+            location(&[("wght", -100.)]),
+            location(&[("wght", -180.)]),
+            location(&[("wdth", 0.3)]),
+            location(&[("wght", 120.), ("wdth", 0.3)]),
+            location(&[("wght", 120.), ("wdth", 0.2)]),
+            location(&[]),
+            location(&[("wght", 180.), ("wdth", 0.3)]),
+            location(&[("wght", 180.)]),
+        ];
+        let model = VariationModel::new(&locations, &["wght".to_string()]).unwrap();
+        assert_eq!(model.order, [6, 1, 2, 0, 8, 3, 7, 4, 5]);
+
+        let expected: [&[(usize, f64)]; 9] = [
+            &[],
+            &[(0, 1.)],
+            &[(0, 1.)],
+            &[(0, 1.)],
+            &[(0, 1.)],
+            &[(0, 1.)],
+            &[(0, 1.), (4, 1.), (5, 1.)],
+            &[(0, 1.), (3, 0.75), (4, 0.25), (5, 1.), (6, 2. / 3.)],
+            &[
+                (0, 1.),
+                (3, 0.75),
+                (4, 0.25),
+                (5, 2. / 3.),
+                (6, 4. / 9.),
+                (7, 2. / 3.),
+            ],
+        ];
+        for (weights, expected) in model.delta_weights.iter().zip(expected) {
+            assert_eq!(
+                weights.len(),
+                expected.len(),
+                "{:?} != {:?}",
+                weights,
+                expected
+            );
+            for (&(j, w), &(ej, ew)) in weights.iter().zip(expected) {
+                assert!(
+                    j == ej && (w - ew).abs() < 1e-9,
+                    "{:?} != {:?}",
+                    weights,
+                    expected
+                );
+            }
+        }
+    }
+}
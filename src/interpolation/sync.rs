@@ -0,0 +1,255 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use glifparser::{Glif, Handle};
+use plist::{Dictionary, Value as Plist};
+
+use crate::{
+    error::LoadError,
+    parsing::glyph_entries::GlyphEntry,
+    viewer::UFO,
+};
+
+/// The lib.plist key listing glyphs that were copied in from another master and haven't been
+/// looked at since.
+pub const NEEDS_REVIEW_KEY: &str = "org.mfek.ufo.needsReview";
+
+/// The lib.plist key set on masters that only have some of the glyphs, such as intermediate
+/// masters correcting a handful of them. The glyphs they lack aren't reported as missing.
+pub const SPARSE_KEY: &str = "org.mfek.ufo.sparse";
+
+/// The glyphs of the UFO at `font_path` that are marked for review. A font without a lib.plist,
+/// or without the key, has none.
+pub fn read_review_marks(font_path: &Path) -> BTreeSet<String> {
+    read_lib(font_path)
+        .and_then(|lib| {
+            let names = lib.get(NEEDS_REVIEW_KEY)?.as_array()?;
+            Some(
+                names
+                    .iter()
+                    .filter_map(Plist::as_string)
+                    .map(str::to_string)
+                    .collect(),
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// Writes `marks` to the lib.plist of the UFO at `font_path`, keeping the rest of it as is.
+pub fn write_review_marks(font_path: &Path, marks: &BTreeSet<String>) -> Result<(), LoadError> {
+    update_lib(font_path, |lib| {
+        if marks.is_empty() {
+            lib.remove(NEEDS_REVIEW_KEY);
+        } else {
+            let names = marks.iter().cloned().map(Plist::String).collect();
+            lib.insert(NEEDS_REVIEW_KEY.to_string(), Plist::Array(names));
+        }
+    })
+    .map_err(|message| copy_error(&font_path.join("lib.plist"), message))
+}
+
+/// Whether the UFO at `font_path` is marked as a sparse master.
+pub fn read_sparse(font_path: &Path) -> bool {
+    read_lib(font_path)
+        .and_then(|lib| lib.get(SPARSE_KEY)?.as_boolean())
+        .unwrap_or(false)
+}
+
+pub fn write_sparse(font_path: &Path, sparse: bool) -> Result<(), LoadError> {
+    update_lib(font_path, |lib| {
+        if sparse {
+            lib.insert(SPARSE_KEY.to_string(), Plist::Boolean(true));
+        } else {
+            lib.remove(SPARSE_KEY);
+        }
+    })
+    .map_err(|message| LoadError::Lib {
+        path: font_path.join("lib.plist"),
+        message,
+    })
+}
+
+fn read_lib(font_path: &Path) -> Option<Dictionary> {
+    plist::from_file(font_path.join("lib.plist")).ok()
+}
+
+// Changes lib.plist with `change`, keeping the rest of it as is.
+fn update_lib(font_path: &Path, change: impl FnOnce(&mut Dictionary)) -> Result<(), String> {
+    let path = font_path.join("lib.plist");
+    let mut lib: Dictionary = if path.exists() {
+        plist::from_file(&path).map_err(|e| e.to_string())?
+    } else {
+        Dictionary::new()
+    };
+    change(&mut lib);
+    plist::to_file_xml(&path, &lib).map_err(|e| e.to_string())
+}
+
+/// Copies `entry`, a glyph of `reference`, into the default layer of `target`: writes its .glif
+/// and adds it to contents.plist. With `scale`, the glyph is scaled by how much taller the
+/// target's ascender-to-descender span is than the reference's. Returns the target's new entry.
+pub fn copy_glyph(
+    entry: &GlyphEntry,
+    reference: &UFO,
+    target: &UFO,
+    scale: bool,
+) -> Result<GlyphEntry, LoadError> {
+    let source = Path::new(&entry.filename);
+
+    // layered .glifjson glyphs are copied flattened, as plain .glif
+    let mut glif = entry.glif.clone();
+
+    if scale {
+        let span = |ufo: &UFO| (ufo.metadata.ascender() - ufo.metadata.descender()).max(1) as f32;
+        scale_glif(&mut glif, span(target) / span(reference));
+    }
+
+    let glyphs_dir = target.path.join("glyphs");
+    let file_name = free_file_name(&glyphs_dir, source, &entry.glifname);
+    let path = glyphs_dir.join(&file_name);
+    glif.filename = Some(path.clone());
+
+    glifparser::write_to_filename(&glif, &path).map_err(|e| copy_error(&path, e.to_string()))?;
+    add_to_contents(&glyphs_dir, &entry.glifname, &file_name)?;
+
+    Ok(GlyphEntry {
+        filename: path.to_string_lossy().into_owned(),
+        glif,
+        layered: None,
+        error: None,
+        ..entry.clone()
+    })
+}
+
+fn copy_error(path: &Path, message: String) -> LoadError {
+    LoadError::Copy {
+        path: path.to_owned(),
+        message,
+    }
+}
+
+// The reference's own file name, unless the target already uses it for another glyph.
+fn free_file_name(glyphs_dir: &Path, source: &Path, glifname: &str) -> String {
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| glifname.to_string());
+    // `a.layered.glifjson` becomes `a.glif`
+    let stem = stem.strip_suffix(".layered").unwrap_or(&stem).to_string();
+
+    let mut file_name = format!("{}.glif", stem);
+    let mut n = 1;
+    while glyphs_dir.join(&file_name).exists() {
+        file_name = format!("{}{}.glif", stem, n);
+        n += 1;
+    }
+    file_name
+}
+
+fn add_to_contents(glyphs_dir: &Path, glifname: &str, file_name: &str) -> Result<(), LoadError> {
+    let path: PathBuf = glyphs_dir.join("contents.plist");
+    let error = |message: String| LoadError::Contents {
+        path: path.clone(),
+        message,
+    };
+
+    let mut contents: Dictionary = if path.exists() {
+        plist::from_file(&path).map_err(|e| error(e.to_string()))?
+    } else {
+        Dictionary::new()
+    };
+    contents.insert(glifname.to_string(), Plist::String(file_name.to_string()));
+    plist::to_file_xml(&path, &contents).map_err(|e| error(e.to_string()))
+}
+
+// Component offsets are left as they are.
+fn scale_glif(glif: &mut Glif<()>, factor: f32) {
+    for point in glif.outline.iter_mut().flatten().flatten() {
+        point.x *= factor;
+        point.y *= factor;
+        for handle in [&mut point.a, &mut point.b] {
+            if let Handle::At(x, y) = handle {
+                *x *= factor;
+                *y *= factor;
+            }
+        }
+    }
+
+    for anchor in &mut glif.anchors {
+        anchor.x *= factor;
+        anchor.y *= factor;
+    }
+
+    if let Some(width) = &mut glif.width {
+        *width = (*width as f32 * factor).round() as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glifparser::{Anchor, Point, PointType};
+    use std::fs;
+
+    #[test]
+    fn scaling_leaves_colocated_handles() {
+        let mut point = Point::from_x_y_type((100., 200.), PointType::Curve);
+        point.a = Handle::At(150., 250.);
+        let mut glif = Glif::new();
+        glif.outline = Some(vec![vec![point]]);
+        glif.anchors = vec![Anchor {
+            x: 50.,
+            y: 700.,
+            ..Default::default()
+        }];
+        glif.width = Some(501);
+
+        scale_glif(&mut glif, 1.5);
+        let point = &glif.outline.as_ref().unwrap()[0][0];
+        assert_eq!((point.x, point.y), (150., 300.));
+        assert_eq!(point.a, Handle::At(225., 375.));
+        assert_eq!(point.b, Handle::Colocated);
+        assert_eq!((glif.anchors[0].x, glif.anchors[0].y), (75., 1050.));
+        assert_eq!(glif.width, Some(752));
+    }
+
+    #[test]
+    fn file_names_dont_clash() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = Path::new("/elsewhere/glyphs/a.glif");
+        assert_eq!(free_file_name(dir.path(), source, "a"), "a.glif");
+
+        fs::write(dir.path().join("a.glif"), "").unwrap();
+        fs::write(dir.path().join("a1.glif"), "").unwrap();
+        assert_eq!(free_file_name(dir.path(), source, "a"), "a2.glif");
+
+        let layered = Path::new("/elsewhere/glyphs/b.layered.glifjson");
+        assert_eq!(free_file_name(dir.path(), layered, "b"), "b.glif");
+    }
+
+    #[test]
+    fn lib_keys_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read_review_marks(dir.path()).is_empty());
+        assert!(!read_sparse(dir.path()));
+
+        let mut lib = Dictionary::new();
+        lib.insert("public.glyphOrder".to_string(), Plist::Array(vec![]));
+        plist::to_file_xml(dir.path().join("lib.plist"), &lib).unwrap();
+
+        let marks: BTreeSet<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+        write_review_marks(dir.path(), &marks).unwrap();
+        write_sparse(dir.path(), true).unwrap();
+        assert_eq!(read_review_marks(dir.path()), marks);
+        assert!(read_sparse(dir.path()));
+
+        write_review_marks(dir.path(), &BTreeSet::new()).unwrap();
+        write_sparse(dir.path(), false).unwrap();
+        assert!(read_review_marks(dir.path()).is_empty());
+        assert!(!read_sparse(dir.path()));
+        // the rest of lib.plist is kept
+        assert_eq!(read_lib(dir.path()), Some(lib));
+    }
+}
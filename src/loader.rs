@@ -0,0 +1,93 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{mpsc::Sender, Arc},
+    thread,
+};
+
+use libmfekufo::{
+    blocks::{self, Block},
+    glyphs,
+};
+
+use crate::{
+    error::LoadError,
+    parsing::{glyph_entries::GlyphEntry, layer::Layer},
+    provider::MetadataProvider,
+};
+
+/// Sent from the loader thread to `UFOViewer`. `load_id` identifies which master the message is
+/// for, so that messages from a load that has since been superseded can be dropped.
+pub struct LoadMessage {
+    pub load_id: u64,
+    pub event: LoadEvent,
+}
+
+pub enum LoadEvent {
+    Glyphs { batch: Vec<GlyphEntry>, total: usize },
+    Layers(Vec<Layer>),
+    UnicodeBlocks(Vec<Block>),
+    Failed(LoadError),
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoadProgress {
+    pub loaded: usize,
+    pub total: usize,
+}
+
+impl LoadProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            0.
+        } else {
+            self.loaded as f32 / self.total as f32
+        }
+    }
+}
+
+/// Parses every glyph of the UFO at `path` on a worker thread, streaming them back over `tx`.
+pub fn spawn(
+    provider: Arc<dyn MetadataProvider>,
+    path: PathBuf,
+    load_id: u64,
+    tx: Sender<LoadMessage>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        // if the receiving end is gone the viewer has exited, so send errors are ignored
+        let send = |event| {
+            let _ = tx.send(LoadMessage { load_id, event });
+        };
+
+        let streamed = provider.stream_glyph_entries(&path, &mut |batch, total| {
+            send(LoadEvent::Glyphs { batch, total })
+        });
+        if let Err(e) = streamed {
+            send(LoadEvent::Failed(e));
+            return;
+        }
+
+        match provider.layers(&path) {
+            Ok(layers) => send(LoadEvent::Layers(layers)),
+            Err(e) => send(LoadEvent::Failed(e)),
+        }
+
+        match unicode_blocks(&path) {
+            Ok(blocks) => send(LoadEvent::UnicodeBlocks(blocks)),
+            Err(e) => send(LoadEvent::Failed(e)),
+        }
+
+        send(LoadEvent::Finished);
+    })
+}
+
+pub fn unicode_blocks<P: AsRef<Path>>(path: P) -> Result<Vec<Block>, LoadError> {
+    let path_str = path.as_ref().to_str().ok_or_else(|| LoadError::Font {
+        path: path.as_ref().to_owned(),
+        message: "path is not valid UTF-8".to_string(),
+    })?;
+    let gvec = glyphs::for_ufo(path_str.to_owned());
+    let unique_encodings = glyphs::to_unique_codepoints(&gvec);
+    let blocks = blocks::for_unicode_data(&unique_encodings);
+    Ok(blocks::grouped_by(&gvec, &blocks))
+}
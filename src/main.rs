@@ -1,19 +1,27 @@
 use egui_sdl2_event::DpiMode;
 
+mod args;
+mod check;
+mod designspace;
+mod error;
+mod generate;
 mod gui;
 mod interface;
 mod ipc;
+mod loader;
 mod parsing;
+mod provider;
 mod ufo_cache;
+mod ufoz;
 mod viewer;
 mod interpolation;
 
 use interface::Interface;
 
 use gui::fontview::fontview;
-use gui::menu::menu;
+use gui::menu::{self, menu};
 
-use crate::{ufo_cache::UFOCache, viewer::UFOViewer};
+use crate::{provider::SubprocessProvider, ufo_cache::UFOCache, viewer::UFOViewer};
 
 /// This is a mix of the rust-sdl2 opengl example,
 /// the skia-safe gl window example: https://github.com/rust-skia/rust-skia/blob/master/skia-safe/examples/gl-window/main.rs
@@ -23,6 +31,8 @@ fn main() {
     extern crate sdl2;
 
     unsafe { backtrace_on_stack_overflow::enable(); }
+    let args = args::parse_args();
+
     use egui_sdl2_event::EguiSDL2State;
     use sdl2::event::{Event, WindowEvent};
     use sdl2::keyboard::Keycode;
@@ -33,8 +43,34 @@ fn main() {
     let mut viewer: UFOViewer = UFOViewer::default();
     let mut cache: UFOCache = UFOCache::default();
 
+    if args.mfekmetadata {
+        match SubprocessProvider::locate() {
+            Some(provider) => viewer.set_provider(Box::new(provider)),
+            None => log::error!("Failed to locate mfekmetadata! Reading fonts in-process instead."),
+        }
+    }
+
+    // headless, so bail out before SDL is brought up
+    if let Some(check_args) = args.check {
+        std::process::exit(check::run(viewer, check_args));
+    }
+    if let Some(generate_args) = args.generate {
+        std::process::exit(generate::run(viewer, generate_args));
+    }
+
     let mut interface = Interface::new((800., 600.));
 
+    if let Some(font) = &args.font {
+        menu::open_font(&mut viewer, &mut interface, font);
+        for master in &args.masters {
+            menu::add_master(&mut viewer, master);
+        }
+    }
+    if let Some(filter) = args.filter {
+        viewer.filter_string = filter;
+    }
+    viewer.filter_block = args.block;
+
     let mut egui_sdl2_state: EguiSDL2State = EguiSDL2State::new(
         &interface.sdl_window,
         &interface.sdl_context.video().unwrap(),
@@ -52,6 +88,10 @@ fn main() {
         if viewer.dirty {
             cache.force_rebuild_all();
             viewer.dirty = false;
+            viewer.dirty_glyphs.clear();
+        }
+        for glifname in viewer.dirty_glyphs.drain() {
+            cache.rebuild_glyph(&glifname);
         }
 
         for event in interface.get_event_pump().poll_iter() {
@@ -0,0 +1,867 @@
+use plist::{Dictionary, Value};
+use serde_json::Value as Json;
+
+/// Conversion between fontinfo.plist values and the Rust types `FontInfo` stores them as, and to
+/// and from the text shown in the Font Info editor. Records are shown as JSON.
+pub trait PlistValue: Sized {
+    // whether a list of these is shown as space separated values rather than as JSON
+    const INLINE: bool = false;
+
+    fn from_plist(value: &Value) -> Option<Self>;
+    fn to_plist(&self) -> Value;
+
+    fn to_text(&self) -> String {
+        plist_to_json(&self.to_plist()).to_string()
+    }
+
+    fn parse_text(text: &str) -> Result<Self, String> {
+        parse_json(text)
+    }
+}
+
+fn parse_json<T: PlistValue>(text: &str) -> Result<T, String> {
+    let json: Json = serde_json::from_str(text).map_err(|e| format!("expected JSON: {}", e))?;
+    json_to_plist(&json)
+        .as_ref()
+        .and_then(T::from_plist)
+        .ok_or_else(|| "expected other keys or values".to_string())
+}
+
+impl PlistValue for String {
+    const INLINE: bool = true;
+
+    fn from_plist(value: &Value) -> Option<Self> {
+        value.as_string().map(str::to_owned)
+    }
+
+    fn to_plist(&self) -> Value {
+        Value::String(self.clone())
+    }
+
+    fn to_text(&self) -> String {
+        self.clone()
+    }
+
+    fn parse_text(text: &str) -> Result<Self, String> {
+        Ok(text.to_owned())
+    }
+}
+
+impl PlistValue for bool {
+    const INLINE: bool = true;
+
+    fn from_plist(value: &Value) -> Option<Self> {
+        value.as_boolean()
+    }
+
+    fn to_plist(&self) -> Value {
+        Value::Boolean(*self)
+    }
+
+    fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    fn parse_text(text: &str) -> Result<Self, String> {
+        text.trim()
+            .parse()
+            .map_err(|_| "expected true or false".to_string())
+    }
+}
+
+impl PlistValue for i32 {
+    const INLINE: bool = true;
+
+    fn from_plist(value: &Value) -> Option<Self> {
+        value.as_signed_integer().and_then(|i| i32::try_from(i).ok())
+    }
+
+    fn to_plist(&self) -> Value {
+        Value::Integer((*self as i64).into())
+    }
+
+    fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    fn parse_text(text: &str) -> Result<Self, String> {
+        text.trim()
+            .parse()
+            .map_err(|_| "expected an integer".to_string())
+    }
+}
+
+impl PlistValue for u32 {
+    const INLINE: bool = true;
+
+    fn from_plist(value: &Value) -> Option<Self> {
+        value.as_signed_integer().and_then(|i| u32::try_from(i).ok())
+    }
+
+    fn to_plist(&self) -> Value {
+        Value::Integer((*self as u64).into())
+    }
+
+    fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    fn parse_text(text: &str) -> Result<Self, String> {
+        text.trim()
+            .parse()
+            .map_err(|_| "expected a non-negative integer".to_string())
+    }
+}
+
+// Numbers may be written as either <integer> or <real>.
+impl PlistValue for f64 {
+    const INLINE: bool = true;
+
+    fn from_plist(value: &Value) -> Option<Self> {
+        value
+            .as_real()
+            .or_else(|| value.as_signed_integer().map(|i| i as f64))
+    }
+
+    fn to_plist(&self) -> Value {
+        if self.fract() == 0. && self.abs() < i64::MAX as f64 {
+            Value::Integer((*self as i64).into())
+        } else {
+            Value::Real(*self)
+        }
+    }
+
+    fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    fn parse_text(text: &str) -> Result<Self, String> {
+        match text.trim().parse::<f64>() {
+            Ok(n) if n.is_finite() => Ok(n),
+            _ => Err("expected a number".to_string()),
+        }
+    }
+}
+
+impl<T: PlistValue> PlistValue for Vec<T> {
+    fn from_plist(value: &Value) -> Option<Self> {
+        value.as_array()?.iter().map(T::from_plist).collect()
+    }
+
+    fn to_plist(&self) -> Value {
+        Value::Array(self.iter().map(PlistValue::to_plist).collect())
+    }
+
+    // Lists of numbers and such are edited as space separated values.
+    fn to_text(&self) -> String {
+        if !T::INLINE {
+            return plist_to_json(&self.to_plist()).to_string();
+        }
+        self.iter()
+            .map(PlistValue::to_text)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn parse_text(text: &str) -> Result<Self, String> {
+        if !T::INLINE {
+            return parse_json(text);
+        }
+        text.split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|s| !s.is_empty())
+            .map(T::parse_text)
+            .collect()
+    }
+}
+
+/// Dates and data, which JSON has nothing for, become null.
+pub fn plist_to_json(value: &Value) -> Json {
+    match value {
+        Value::Boolean(b) => Json::Bool(*b),
+        Value::Integer(i) => match i.as_signed() {
+            Some(i) => Json::from(i),
+            None => i.as_unsigned().map(Json::from).unwrap_or(Json::Null),
+        },
+        Value::Real(r) => Json::from(*r),
+        Value::String(s) => Json::String(s.clone()),
+        Value::Array(a) => Json::Array(a.iter().map(plist_to_json).collect()),
+        Value::Dictionary(d) => {
+            Json::Object(d.iter().map(|(k, v)| (k.clone(), plist_to_json(v))).collect())
+        }
+        _ => Json::Null,
+    }
+}
+
+/// Nulls are dropped, as plists have no null.
+pub fn json_to_plist(value: &Json) -> Option<Value> {
+    match value {
+        Json::Null => None,
+        Json::Bool(b) => Some(Value::Boolean(*b)),
+        Json::Number(n) => match n.as_i64() {
+            Some(i) => Some(Value::Integer(i.into())),
+            None => n.as_f64().map(Value::Real),
+        },
+        Json::String(s) => Some(Value::String(s.clone())),
+        Json::Array(a) => Some(Value::Array(a.iter().filter_map(json_to_plist).collect())),
+        Json::Object(o) => Some(Value::Dictionary(
+            o.iter()
+                .filter_map(|(k, v)| json_to_plist(v).map(|v| (k.clone(), v)))
+                .collect(),
+        )),
+    }
+}
+
+// Records are dictionaries with fixed keys, all of them optional here. A key holding a value of
+// the wrong type makes the whole record unreadable.
+macro_rules! record {
+    ($($(#[doc = $doc:literal])* $name:ident { $($field:ident: $ty:ty = $key:literal,)* })*) => {
+        $(
+            $(#[doc = $doc])*
+            #[derive(Debug, Clone, Default, PartialEq)]
+            pub struct $name {
+                $(pub $field: Option<$ty>,)*
+            }
+
+            impl PlistValue for $name {
+                fn from_plist(value: &Value) -> Option<Self> {
+                    let dict = value.as_dictionary()?;
+                    Some($name {
+                        $($field: match dict.get($key).map(<$ty>::from_plist) {
+                            Some(None) => return None,
+                            value => value.flatten(),
+                        },)*
+                    })
+                }
+
+                fn to_plist(&self) -> Value {
+                    let mut dict = Dictionary::new();
+                    $(if let Some(value) = &self.$field {
+                        dict.insert($key.to_string(), value.to_plist());
+                    })*
+                    Value::Dictionary(dict)
+                }
+            }
+        )*
+    };
+}
+
+record! {
+    /// A font-wide guideline. Vertical ones only have an x, horizontal ones only a y.
+    Guideline {
+        x: f64 = "x",
+        y: f64 = "y",
+        angle: f64 = "angle",
+        name: String = "name",
+        color: String = "color",
+        identifier: String = "identifier",
+    }
+    /// A name table record beyond those fontinfo.plist has keys for.
+    NameRecord {
+        name_id: u32 = "nameID",
+        platform_id: u32 = "platformID",
+        encoding_id: u32 = "encodingID",
+        language_id: u32 = "languageID",
+        string: String = "string",
+    }
+    /// The rasterizer behaviors of the gasp table, as bit numbers, up to a size in ppem.
+    GaspRangeRecord {
+        range_max_ppem: u32 = "rangeMaxPPEM",
+        range_gasp_behavior: Vec<u32> = "rangeGaspBehavior",
+    }
+    WoffUniqueId {
+        id: String = "id",
+    }
+    WoffVendor {
+        name: String = "name",
+        url: String = "url",
+        dir: String = "dir",
+        class: String = "class",
+    }
+    WoffCredits {
+        credits: Vec<WoffCredit> = "credits",
+    }
+    WoffCredit {
+        name: String = "name",
+        url: String = "url",
+        role: String = "role",
+        dir: String = "dir",
+        class: String = "class",
+    }
+    WoffDescription {
+        url: String = "url",
+        text: Vec<WoffText> = "text",
+    }
+    WoffLicense {
+        url: String = "url",
+        id: String = "id",
+        text: Vec<WoffText> = "text",
+    }
+    WoffCopyright {
+        text: Vec<WoffText> = "text",
+    }
+    WoffTrademark {
+        text: Vec<WoffText> = "text",
+    }
+    WoffLicensee {
+        name: String = "name",
+        dir: String = "dir",
+        class: String = "class",
+    }
+    /// Text in one language, as the WOFF metadata has it for descriptions, licenses and
+    /// extension names and values.
+    WoffText {
+        text: String = "text",
+        language: String = "language",
+        dir: String = "dir",
+        class: String = "class",
+    }
+    WoffExtension {
+        id: String = "id",
+        names: Vec<WoffText> = "names",
+        items: Vec<WoffExtensionItem> = "items",
+    }
+    WoffExtensionItem {
+        id: String = "id",
+        names: Vec<WoffText> = "names",
+        values: Vec<WoffText> = "values",
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldGroup {
+    Naming,
+    Dimensions,
+    OpenType,
+    PostScript,
+    Macintosh,
+    Woff,
+}
+
+impl FieldGroup {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FieldGroup::Naming => "Naming",
+            FieldGroup::Dimensions => "Dimensions",
+            FieldGroup::OpenType => "OpenType tables",
+            FieldGroup::PostScript => "PostScript",
+            FieldGroup::Macintosh => "Macintosh FOND resource",
+            FieldGroup::Woff => "WOFF",
+        }
+    }
+}
+
+macro_rules! fontinfo {
+    ($($group:ident { $($field:ident: $ty:ty = $key:literal,)* })*) => {
+        /// Every fontinfo.plist key of the UFO 3 spec. Every key is optional, as it is in the
+        /// spec. Keys from outside the spec are kept in `Metadata::raw`.
+        #[derive(Debug, Clone, Default, PartialEq)]
+        pub struct FontInfo {
+            $($(pub $field: Option<$ty>,)*)*
+        }
+
+        impl FontInfo {
+            /// Every known key, in the order the Font Info editor shows them.
+            pub const FIELDS: &'static [(FieldGroup, &'static str)] = &[
+                $($((FieldGroup::$group, $key),)*)*
+            ];
+
+            /// The value of `key` as shown in the editor, or `None` if it's unset.
+            pub fn get_text(&self, key: &str) -> Option<String> {
+                match key {
+                    $($($key => self.$field.as_ref().map(PlistValue::to_text),)*)*
+                    _ => None,
+                }
+            }
+
+            /// Parses `text` into `key`. Empty text unsets the key.
+            pub fn set_text(&mut self, key: &str, text: &str) -> Result<(), String> {
+                match key {
+                    $($($key => {
+                        self.$field = if text.trim().is_empty() {
+                            None
+                        } else {
+                            Some(<$ty>::parse_text(text)?)
+                        };
+                    })*)*
+                    _ => return Err(format!("unknown key {}", key)),
+                }
+                Ok(())
+            }
+
+            /// A known key holding a value of the wrong type is left unset, with a warning. Its
+            /// value stays in `dict`, and `write_to_dict` won't touch it.
+            pub fn from_dict(dict: &Dictionary) -> Self {
+                let mut info = FontInfo::default();
+                $($(
+                    if let Some(value) = dict.get($key) {
+                        info.$field = <$ty>::from_plist(value);
+                        if info.$field.is_none() {
+                            log::warn!("Ignoring fontinfo key {}, it has the wrong type ({:?})", $key, value);
+                        }
+                    }
+                )*)*
+                info
+            }
+
+            /// Writes every known key into `dict`, removing the ones that are unset, and leaves
+            /// all other keys alone. Unset keys whose value in `dict` has the wrong type were
+            /// skipped by `from_dict` and are kept as they are.
+            pub fn write_to_dict(&self, dict: &mut Dictionary) {
+                $($(
+                    match &self.$field {
+                        Some(value) => {
+                            dict.insert($key.to_string(), value.to_plist());
+                        }
+                        None => {
+                            if dict.get($key).is_some_and(|v| <$ty>::from_plist(v).is_some()) {
+                                dict.remove($key);
+                            }
+                        }
+                    }
+                )*)*
+            }
+        }
+    };
+}
+
+fontinfo! {
+    Naming {
+        family_name: String = "familyName",
+        style_name: String = "styleName",
+        style_map_family_name: String = "styleMapFamilyName",
+        style_map_style_name: String = "styleMapStyleName",
+        version_major: i32 = "versionMajor",
+        version_minor: u32 = "versionMinor",
+        year: i32 = "year",
+        copyright: String = "copyright",
+        trademark: String = "trademark",
+        note: String = "note",
+        open_type_name_designer: String = "openTypeNameDesigner",
+        open_type_name_designer_url: String = "openTypeNameDesignerURL",
+        open_type_name_manufacturer: String = "openTypeNameManufacturer",
+        open_type_name_manufacturer_url: String = "openTypeNameManufacturerURL",
+        open_type_name_license: String = "openTypeNameLicense",
+        open_type_name_license_url: String = "openTypeNameLicenseURL",
+        open_type_name_version: String = "openTypeNameVersion",
+        open_type_name_unique_id: String = "openTypeNameUniqueID",
+        open_type_name_description: String = "openTypeNameDescription",
+        open_type_name_preferred_family_name: String = "openTypeNamePreferredFamilyName",
+        open_type_name_preferred_subfamily_name: String = "openTypeNamePreferredSubfamilyName",
+        open_type_name_sample_text: String = "openTypeNameSampleText",
+        open_type_name_compatible_full_name: String = "openTypeNameCompatibleFullName",
+        open_type_name_wws_family_name: String = "openTypeNameWWSFamilyName",
+        open_type_name_wws_subfamily_name: String = "openTypeNameWWSSubfamilyName",
+        open_type_name_records: Vec<NameRecord> = "openTypeNameRecords",
+    }
+    Dimensions {
+        units_per_em: f64 = "unitsPerEm",
+        descender: f64 = "descender",
+        x_height: f64 = "xHeight",
+        cap_height: f64 = "capHeight",
+        ascender: f64 = "ascender",
+        italic_angle: f64 = "italicAngle",
+        guidelines: Vec<Guideline> = "guidelines",
+    }
+    OpenType {
+        open_type_head_created: String = "openTypeHeadCreated",
+        open_type_head_lowest_rec_ppem: u32 = "openTypeHeadLowestRecPPEM",
+        open_type_head_flags: Vec<u32> = "openTypeHeadFlags",
+        open_type_hhea_ascender: i32 = "openTypeHheaAscender",
+        open_type_hhea_descender: i32 = "openTypeHheaDescender",
+        open_type_hhea_line_gap: i32 = "openTypeHheaLineGap",
+        open_type_hhea_caret_slope_rise: i32 = "openTypeHheaCaretSlopeRise",
+        open_type_hhea_caret_slope_run: i32 = "openTypeHheaCaretSlopeRun",
+        open_type_hhea_caret_offset: i32 = "openTypeHheaCaretOffset",
+        open_type_vhea_vert_typo_ascender: i32 = "openTypeVheaVertTypoAscender",
+        open_type_vhea_vert_typo_descender: i32 = "openTypeVheaVertTypoDescender",
+        open_type_vhea_vert_typo_line_gap: i32 = "openTypeVheaVertTypoLineGap",
+        open_type_vhea_caret_slope_rise: i32 = "openTypeVheaCaretSlopeRise",
+        open_type_vhea_caret_slope_run: i32 = "openTypeVheaCaretSlopeRun",
+        open_type_vhea_caret_offset: i32 = "openTypeVheaCaretOffset",
+        open_type_os2_width_class: u32 = "openTypeOS2WidthClass",
+        open_type_os2_weight_class: u32 = "openTypeOS2WeightClass",
+        open_type_os2_selection: Vec<u32> = "openTypeOS2Selection",
+        open_type_os2_vendor_id: String = "openTypeOS2VendorID",
+        open_type_os2_panose: Vec<u32> = "openTypeOS2Panose",
+        open_type_os2_family_class: Vec<u32> = "openTypeOS2FamilyClass",
+        open_type_os2_unicode_ranges: Vec<u32> = "openTypeOS2UnicodeRanges",
+        open_type_os2_code_page_ranges: Vec<u32> = "openTypeOS2CodePageRanges",
+        open_type_os2_typo_ascender: i32 = "openTypeOS2TypoAscender",
+        open_type_os2_typo_descender: i32 = "openTypeOS2TypoDescender",
+        open_type_os2_typo_line_gap: i32 = "openTypeOS2TypoLineGap",
+        open_type_os2_win_ascent: u32 = "openTypeOS2WinAscent",
+        open_type_os2_win_descent: u32 = "openTypeOS2WinDescent",
+        open_type_os2_type: Vec<u32> = "openTypeOS2Type",
+        open_type_os2_subscript_x_size: i32 = "openTypeOS2SubscriptXSize",
+        open_type_os2_subscript_y_size: i32 = "openTypeOS2SubscriptYSize",
+        open_type_os2_subscript_x_offset: i32 = "openTypeOS2SubscriptXOffset",
+        open_type_os2_subscript_y_offset: i32 = "openTypeOS2SubscriptYOffset",
+        open_type_os2_superscript_x_size: i32 = "openTypeOS2SuperscriptXSize",
+        open_type_os2_superscript_y_size: i32 = "openTypeOS2SuperscriptYSize",
+        open_type_os2_superscript_x_offset: i32 = "openTypeOS2SuperscriptXOffset",
+        open_type_os2_superscript_y_offset: i32 = "openTypeOS2SuperscriptYOffset",
+        open_type_os2_strikeout_size: i32 = "openTypeOS2StrikeoutSize",
+        open_type_os2_strikeout_position: i32 = "openTypeOS2StrikeoutPosition",
+        open_type_gasp_range_records: Vec<GaspRangeRecord> = "openTypeGaspRangeRecords",
+    }
+    PostScript {
+        postscript_font_name: String = "postscriptFontName",
+        postscript_full_name: String = "postscriptFullName",
+        postscript_weight_name: String = "postscriptWeightName",
+        postscript_slant_angle: f64 = "postscriptSlantAngle",
+        postscript_unique_id: i32 = "postscriptUniqueID",
+        postscript_underline_thickness: f64 = "postscriptUnderlineThickness",
+        postscript_underline_position: f64 = "postscriptUnderlinePosition",
+        postscript_is_fixed_pitch: bool = "postscriptIsFixedPitch",
+        postscript_blue_values: Vec<f64> = "postscriptBlueValues",
+        postscript_other_blues: Vec<f64> = "postscriptOtherBlues",
+        postscript_family_blues: Vec<f64> = "postscriptFamilyBlues",
+        postscript_family_other_blues: Vec<f64> = "postscriptFamilyOtherBlues",
+        postscript_stem_snap_h: Vec<f64> = "postscriptStemSnapH",
+        postscript_stem_snap_v: Vec<f64> = "postscriptStemSnapV",
+        postscript_blue_fuzz: f64 = "postscriptBlueFuzz",
+        postscript_blue_shift: f64 = "postscriptBlueShift",
+        postscript_blue_scale: f64 = "postscriptBlueScale",
+        postscript_force_bold: bool = "postscriptForceBold",
+        postscript_default_width_x: f64 = "postscriptDefaultWidthX",
+        postscript_nominal_width_x: f64 = "postscriptNominalWidthX",
+        postscript_default_character: String = "postscriptDefaultCharacter",
+        postscript_windows_character_set: u32 = "postscriptWindowsCharacterSet",
+    }
+    Macintosh {
+        macintosh_fond_family_id: i32 = "macintoshFONDFamilyID",
+        macintosh_fond_name: String = "macintoshFONDName",
+    }
+    Woff {
+        woff_major_version: u32 = "woffMajorVersion",
+        woff_minor_version: u32 = "woffMinorVersion",
+        woff_metadata_unique_id: WoffUniqueId = "woffMetadataUniqueID",
+        woff_metadata_vendor: WoffVendor = "woffMetadataVendor",
+        woff_metadata_credits: WoffCredits = "woffMetadataCredits",
+        woff_metadata_description: WoffDescription = "woffMetadataDescription",
+        woff_metadata_license: WoffLicense = "woffMetadataLicense",
+        woff_metadata_copyright: WoffCopyright = "woffMetadataCopyright",
+        woff_metadata_trademark: WoffTrademark = "woffMetadataTrademark",
+        woff_metadata_licensee: WoffLicensee = "woffMetadataLicensee",
+        woff_metadata_extensions: Vec<WoffExtension> = "woffMetadataExtensions",
+    }
+}
+
+// Bit fields are stored as lists of set bit numbers.
+fn check_bits(problems: &mut Vec<(&'static str, String)>, key: &'static str, bits: &Option<Vec<u32>>, allowed: &[u32]) {
+    if let Some(bits) = bits {
+        if let Some(bit) = bits.iter().find(|b| !allowed.contains(b)) {
+            problems.push((key, format!("bit {} may not be set", bit)));
+        }
+    }
+}
+
+fn check_range<T: PartialOrd + std::fmt::Display>(
+    problems: &mut Vec<(&'static str, String)>,
+    key: &'static str,
+    value: &Option<T>,
+    min: T,
+    max: T,
+) {
+    if let Some(value) = value {
+        if *value < min || *value > max {
+            problems.push((key, format!("must be between {} and {}", min, max)));
+        }
+    }
+}
+
+impl FontInfo {
+    /// Checks the value constraints of the UFO 3 spec. Returns (key, problem) pairs, empty if all
+    /// is well.
+    pub fn validate(&self) -> Vec<(&'static str, String)> {
+        let mut problems = Vec::new();
+
+        if let Some(upm) = self.units_per_em {
+            if upm <= 0. {
+                problems.push(("unitsPerEm", "must be greater than zero".to_string()));
+            }
+        }
+        check_range(&mut problems, "italicAngle", &self.italic_angle, -90., 90.);
+        check_range(&mut problems, "postscriptSlantAngle", &self.postscript_slant_angle, -90., 90.);
+        check_range(&mut problems, "openTypeOS2WeightClass", &self.open_type_os2_weight_class, 1, 1000);
+        check_range(&mut problems, "openTypeOS2WidthClass", &self.open_type_os2_width_class, 1, 9);
+        check_range(&mut problems, "openTypeHeadLowestRecPPEM", &self.open_type_head_lowest_rec_ppem, 0, u16::MAX as u32);
+
+        if let Some(style) = &self.style_map_style_name {
+            if !["regular", "italic", "bold", "bold italic"].contains(&style.as_str()) {
+                problems.push((
+                    "styleMapStyleName",
+                    "must be regular, italic, bold or bold italic".to_string(),
+                ));
+            }
+        }
+
+        if let Some(vendor) = &self.open_type_os2_vendor_id {
+            if vendor.chars().count() > 4 || !vendor.is_ascii() {
+                problems.push(("openTypeOS2VendorID", "must be at most 4 ASCII characters".to_string()));
+            }
+        }
+
+        if let Some(panose) = &self.open_type_os2_panose {
+            if panose.len() != 10 {
+                problems.push(("openTypeOS2Panose", "must have exactly 10 values".to_string()));
+            }
+        }
+
+        if let Some(class) = &self.open_type_os2_family_class {
+            if class.len() != 2 || class[0] > 14 || class[1] > 15 {
+                problems.push((
+                    "openTypeOS2FamilyClass",
+                    "must be a class (0–14) and a subclass (0–15)".to_string(),
+                ));
+            }
+        }
+
+        check_bits(&mut problems, "openTypeHeadFlags", &self.open_type_head_flags, &(0..16).collect::<Vec<_>>());
+        check_bits(&mut problems, "openTypeOS2Selection", &self.open_type_os2_selection, &[1, 2, 3, 4, 7, 8, 9]);
+        check_bits(&mut problems, "openTypeOS2Type", &self.open_type_os2_type, &[1, 2, 3, 8, 9]);
+        check_bits(&mut problems, "openTypeOS2UnicodeRanges", &self.open_type_os2_unicode_ranges, &(0..=122).collect::<Vec<_>>());
+        check_bits(&mut problems, "openTypeOS2CodePageRanges", &self.open_type_os2_code_page_ranges, &(0..64).collect::<Vec<_>>());
+
+        for (key, blues, max_len) in [
+            ("postscriptBlueValues", &self.postscript_blue_values, 14),
+            ("postscriptOtherBlues", &self.postscript_other_blues, 10),
+            ("postscriptFamilyBlues", &self.postscript_family_blues, 14),
+            ("postscriptFamilyOtherBlues", &self.postscript_family_other_blues, 10),
+        ] {
+            if let Some(blues) = blues {
+                if blues.len() % 2 != 0 || blues.len() > max_len {
+                    problems.push((key, format!("must be an even number of values, at most {}", max_len)));
+                }
+            }
+        }
+
+        if let Some(guidelines) = &self.guidelines {
+            let valid = |g: &Guideline| match (g.x, g.y, g.angle) {
+                (Some(_), Some(_), Some(angle)) => (0. ..=360.).contains(&angle),
+                (x, y, None) => x.is_some() || y.is_some(),
+                _ => false,
+            };
+            if !guidelines.iter().all(valid) {
+                problems.push((
+                    "guidelines",
+                    "each needs an x or a y, or both along with an angle of 0–360".to_string(),
+                ));
+            }
+        }
+
+        if let Some(records) = &self.open_type_name_records {
+            let complete = |r: &NameRecord| {
+                r.name_id.is_some()
+                    && r.platform_id.is_some()
+                    && r.encoding_id.is_some()
+                    && r.language_id.is_some()
+                    && r.string.is_some()
+            };
+            if !records.iter().all(complete) {
+                problems.push((
+                    "openTypeNameRecords",
+                    "each needs a nameID, platformID, encodingID, languageID and string".to_string(),
+                ));
+            }
+        }
+
+        if let Some(records) = &self.open_type_gasp_range_records {
+            let valid = |r: &GaspRangeRecord| {
+                r.range_max_ppem.is_some_and(|ppem| ppem <= u16::MAX as u32)
+                    && r.range_gasp_behavior.iter().flatten().all(|bit| *bit <= 3)
+            };
+            if !records.iter().all(valid) {
+                problems.push((
+                    "openTypeGaspRangeRecords",
+                    "each needs a rangeMaxPPEM, and may only set behavior bits 0–3".to_string(),
+                ));
+            }
+        }
+
+        for (key, stems) in [
+            ("postscriptStemSnapH", &self.postscript_stem_snap_h),
+            ("postscriptStemSnapV", &self.postscript_stem_snap_v),
+        ] {
+            if let Some(stems) = stems {
+                if stems.len() > 12 {
+                    problems.push((key, "must have at most 12 values".to_string()));
+                }
+            }
+        }
+
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem_keys(info: &FontInfo) -> Vec<&'static str> {
+        info.validate().into_iter().map(|(key, _)| key).collect()
+    }
+
+    #[test]
+    fn os2_selection_bits() {
+        let mut info = FontInfo {
+            open_type_os2_selection: Some(vec![1, 2, 3, 4, 7, 8, 9]),
+            ..Default::default()
+        };
+        assert!(problem_keys(&info).is_empty());
+
+        for bit in [0, 5, 6] {
+            info.open_type_os2_selection = Some(vec![bit]);
+            assert_eq!(problem_keys(&info), vec!["openTypeOS2Selection"]);
+        }
+    }
+
+    #[test]
+    fn os2_unicode_ranges() {
+        let mut info = FontInfo {
+            open_type_os2_unicode_ranges: Some(vec![0, 122]),
+            ..Default::default()
+        };
+        assert!(problem_keys(&info).is_empty());
+
+        info.open_type_os2_unicode_ranges = Some(vec![123]);
+        assert_eq!(problem_keys(&info), vec!["openTypeOS2UnicodeRanges"]);
+    }
+
+    #[test]
+    fn wrong_type_is_skipped_and_kept() {
+        let mut dict = Dictionary::new();
+        dict.insert("familyName".to_string(), Value::String("KJV1611".to_string()));
+        dict.insert("unitsPerEm".to_string(), Value::String("a thousand".to_string()));
+
+        let mut info = FontInfo::from_dict(&dict);
+        assert_eq!(info.family_name.as_deref(), Some("KJV1611"));
+        assert_eq!(info.units_per_em, None);
+
+        info.family_name = None;
+        info.write_to_dict(&mut dict);
+        assert!(!dict.contains_key("familyName"));
+        assert_eq!(dict.get("unitsPerEm"), Some(&Value::String("a thousand".to_string())));
+    }
+
+    const COMPLETE_FONTINFO: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>familyName</key><string>Fixture</string>
+  <key>unitsPerEm</key><integer>1000</integer>
+  <key>guidelines</key>
+  <array>
+    <dict><key>y</key><real>714.5</real><key>name</key><string>x-height</string></dict>
+    <dict>
+      <key>x</key><integer>100</integer><key>y</key><integer>0</integer>
+      <key>angle</key><real>12.5</real>
+      <key>color</key><string>1,0,0,1</string><key>identifier</key><string>slant</string>
+    </dict>
+  </array>
+  <key>openTypeVheaVertTypoAscender</key><integer>500</integer>
+  <key>openTypeVheaVertTypoDescender</key><integer>-500</integer>
+  <key>openTypeVheaVertTypoLineGap</key><integer>0</integer>
+  <key>openTypeVheaCaretSlopeRise</key><integer>0</integer>
+  <key>openTypeVheaCaretSlopeRun</key><integer>1</integer>
+  <key>openTypeVheaCaretOffset</key><integer>0</integer>
+  <key>openTypeNameCompatibleFullName</key><string>Fixture Bold</string>
+  <key>openTypeNameWWSFamilyName</key><string>Fixture</string>
+  <key>openTypeNameWWSSubfamilyName</key><string>Bold</string>
+  <key>openTypeNameRecords</key>
+  <array>
+    <dict>
+      <key>nameID</key><integer>256</integer><key>platformID</key><integer>3</integer>
+      <key>encodingID</key><integer>1</integer><key>languageID</key><integer>1033</integer>
+      <key>string</key><string>Alternate a</string>
+    </dict>
+  </array>
+  <key>openTypeGaspRangeRecords</key>
+  <array>
+    <dict>
+      <key>rangeMaxPPEM</key><integer>8</integer>
+      <key>rangeGaspBehavior</key><array><integer>1</integer><integer>3</integer></array>
+    </dict>
+    <dict>
+      <key>rangeMaxPPEM</key><integer>65535</integer>
+      <key>rangeGaspBehavior</key><array><integer>0</integer><integer>1</integer></array>
+    </dict>
+  </array>
+  <key>macintoshFONDFamilyID</key><integer>15000</integer>
+  <key>macintoshFONDName</key><string>Fixture</string>
+  <key>woffMajorVersion</key><integer>1</integer>
+  <key>woffMinorVersion</key><integer>2</integer>
+  <key>woffMetadataUniqueID</key><dict><key>id</key><string>org.example.fixture</string></dict>
+  <key>woffMetadataVendor</key>
+  <dict><key>name</key><string>MFEK</string><key>url</key><string>https://mfek.org</string></dict>
+  <key>woffMetadataCredits</key>
+  <dict>
+    <key>credits</key>
+    <array>
+      <dict><key>name</key><string>A. Designer</string><key>role</key><string>Designer</string></dict>
+    </array>
+  </dict>
+  <key>woffMetadataDescription</key>
+  <dict>
+    <key>url</key><string>https://example.org</string>
+    <key>text</key>
+    <array>
+      <dict><key>text</key><string>A test font.</string><key>language</key><string>en</string></dict>
+      <dict><key>text</key><string>خط</string><key>dir</key><string>rtl</string></dict>
+    </array>
+  </dict>
+  <key>woffMetadataLicense</key>
+  <dict>
+    <key>id</key><string>OFL</string>
+    <key>text</key><array><dict><key>text</key><string>SIL OFL 1.1</string></dict></array>
+  </dict>
+  <key>woffMetadataCopyright</key>
+  <dict><key>text</key><array><dict><key>text</key><string>© MFEK</string></dict></array></dict>
+  <key>woffMetadataTrademark</key>
+  <dict><key>text</key><array><dict><key>text</key><string>Fixture™</string></dict></array></dict>
+  <key>woffMetadataLicensee</key><dict><key>name</key><string>Anyone</string></dict>
+  <key>woffMetadataExtensions</key>
+  <array>
+    <dict>
+      <key>id</key><string>glyphs</string>
+      <key>names</key><array><dict><key>text</key><string>Glyphs</string></dict></array>
+      <key>items</key>
+      <array>
+        <dict>
+          <key>names</key><array><dict><key>text</key><string>Count</string></dict></array>
+          <key>values</key><array><dict><key>text</key><string>3</string></dict></array>
+        </dict>
+      </array>
+    </dict>
+  </array>
+</dict>
+</plist>
+"#;
+
+    #[test]
+    fn complete_fontinfo_round_trips() {
+        let dict: Dictionary = plist::from_bytes(COMPLETE_FONTINFO.as_bytes()).unwrap();
+        let info = FontInfo::from_dict(&dict);
+        for key in dict.keys() {
+            assert!(info.get_text(key).is_some(), "{} wasn't read", key);
+        }
+        assert_eq!(problem_keys(&info), Vec::<&str>::new());
+        let credits = info.woff_metadata_credits.as_ref().and_then(|c| c.credits.as_ref());
+        assert_eq!(credits.unwrap()[0].role.as_deref(), Some("Designer"));
+
+        let mut written = Dictionary::new();
+        info.write_to_dict(&mut written);
+        assert_eq!(written, dict);
+
+        let mut xml = Vec::new();
+        plist::to_writer_xml(&mut xml, &written).unwrap();
+        assert_eq!(FontInfo::from_dict(&plist::from_bytes(&xml).unwrap()), info);
+
+        // and through the text the editor shows
+        let mut edited = FontInfo::default();
+        for key in dict.keys() {
+            edited.set_text(key, &info.get_text(key).unwrap()).unwrap();
+        }
+        assert_eq!(edited, info);
+    }
+}
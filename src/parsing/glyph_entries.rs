@@ -1,47 +1,142 @@
 use csv::{ReaderBuilder, StringRecord};
-use glifparser::Glif;
+use glifparser::{Glif, MFEKGlif};
 use std::collections::HashMap;
-use std::error::Error;
+use std::convert::TryFrom;
+use std::fs;
 use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::error::LoadError;
+
+/// Extension of MFEK's layered glyphs, which are `MFEKGlif`s serialized as JSON.
+pub const GLIFJSON_EXTENSION: &str = "glifjson";
+
+#[derive(Debug, Clone)]
 pub struct GlyphEntry {
     pub glifname: String,
     pub codepoints: String,
     pub uniname: String,
     pub unicat: String,
     pub filename: String,
+    // for a .glifjson, the visible layers merged into one outline
     pub glif: Glif<()>,
+    // the glyph as read from a .glifjson, with all of its layers and layer operations
+    pub layered: Option<MFEKGlif<()>>,
+    // set if the .glif couldn't be parsed, in which case `glif` is empty
+    pub error: Option<LoadError>,
 }
 
-impl From<(&HashMap<String, usize>, &StringRecord)> for GlyphEntry {
-    fn from((header_map, record): (&HashMap<String, usize>, &StringRecord)) -> Self {
-        let filename = record
-            .get(*header_map.get("filename").unwrap())
-            .unwrap()
-            .to_string();
+impl GlyphEntry {
+    /// Reads the .glif or .glifjson at `filename`. A glyph that fails to parse is still returned,
+    /// with an empty outline and `error` set, so that one bad file doesn't keep the rest of the
+    /// font from opening.
+    pub fn load(
+        mut glifname: String,
+        codepoints: String,
+        uniname: String,
+        unicat: String,
+        filename: String,
+    ) -> Self {
+        let (glif, layered, error) = match read_glyph(Path::new(&filename)) {
+            Ok((glif, layered)) => {
+                // a .glifjson found outside contents.plist is only named by its contents
+                if layered.is_some() && !glif.name.is_empty() {
+                    glifname = glif.name.clone();
+                }
+                (glif, layered, None)
+            }
+            Err(e) => {
+                log::error!("Failed to load glyph {}: {}", filename, e);
+                let mut glif = Glif::new();
+                glif.name = glifname.clone();
+                glif.filename = Some(PathBuf::from(&filename));
+                let error = LoadError::Glif {
+                    path: PathBuf::from(&filename),
+                    message: e,
+                };
+                (glif, None, Some(error))
+            }
+        };
 
         GlyphEntry {
-            glifname: record
-                .get(*header_map.get("glifname").unwrap())
-                .unwrap()
-                .to_string(),
-            codepoints: record
-                .get(*header_map.get("codepoints").unwrap())
-                .unwrap()
-                .to_string(),
-            uniname: record
-                .get(*header_map.get("uniname").unwrap())
-                .unwrap()
-                .to_string(),
-            unicat: record
-                .get(*header_map.get("unicat").unwrap())
-                .unwrap()
-                .to_string(),
-            filename: filename.clone(),
-            glif: glifparser::read_from_filename(filename).expect("Failed to load glyph!"),
+            glifname,
+            codepoints,
+            uniname,
+            unicat,
+            filename,
+            glif,
+            layered,
+            error,
         }
     }
+
+    pub fn is_broken(&self) -> bool {
+        self.error.is_some()
+    }
+}
+
+pub fn is_glifjson(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == GLIFJSON_EXTENSION)
+}
+
+/// Reads a .glif, or a .glifjson along with the flattened `Glif` of its visible layers.
+pub fn read_glyph(path: &Path) -> Result<(Glif<()>, Option<MFEKGlif<()>>), String> {
+    if !is_glifjson(path) {
+        return glifparser::read_from_filename(path)
+            .map(|glif| (glif, None))
+            .map_err(|e| e.to_string());
+    }
+
+    let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut layered: MFEKGlif<()> = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    layered.filename = Some(path.to_owned());
+
+    let mut glif = Glif::from(layered.clone());
+    glif.filename = Some(path.to_owned());
+    Ok((glif, Some(layered)))
+}
+
+impl TryFrom<(&HashMap<String, usize>, &StringRecord)> for GlyphEntry {
+    type Error = LoadError;
+
+    fn try_from(
+        (header_map, record): (&HashMap<String, usize>, &StringRecord),
+    ) -> Result<Self, Self::Error> {
+        let column = |name: &str| -> Result<String, LoadError> {
+            header_map
+                .get(name)
+                .and_then(|i| record.get(*i))
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    LoadError::Subprocess(format!(
+                        "missing column `{}` in glyph list (line {:?})",
+                        name,
+                        record.position().map(|p| p.line())
+                    ))
+                })
+        };
+
+        Ok(GlyphEntry::load(
+            column("glifname")?,
+            column("codepoints")?,
+            column("uniname")?,
+            column("unicat")?,
+            column("filename")?,
+        ))
+    }
+}
+
+// `layered` is left out: it's what `glif` was flattened from.
+impl PartialEq for GlyphEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.glifname == other.glifname
+            && self.codepoints == other.codepoints
+            && self.uniname == other.uniname
+            && self.unicat == other.unicat
+            && self.filename == other.filename
+            && self.glif == other.glif
+            && self.error == other.error
+    }
 }
 
 impl Eq for GlyphEntry {
@@ -54,13 +149,15 @@ impl Hash for GlyphEntry {
 }
 
 
-pub fn parse_tsv(tsv_data: &str) -> Result<Vec<GlyphEntry>, Box<dyn Error>> {
+pub fn parse_tsv(tsv_data: &str) -> Result<Vec<GlyphEntry>, LoadError> {
+    let tsv_error = |e: csv::Error| LoadError::Subprocess(format!("malformed glyph list: {}", e));
+
     let mut reader = ReaderBuilder::new()
         .delimiter(b'\t')
         .from_reader(tsv_data.as_bytes());
 
     let header_map = {
-        let headers = reader.headers()?.iter().enumerate();
+        let headers = reader.headers().map_err(tsv_error)?.iter().enumerate();
         let mut header_map = HashMap::new();
         for (i, header) in headers {
             header_map.insert(header.to_string(), i);
@@ -70,10 +167,10 @@ pub fn parse_tsv(tsv_data: &str) -> Result<Vec<GlyphEntry>, Box<dyn Error>> {
 
     let mut data: Vec<GlyphEntry> = Vec::new();
     for result in reader.records() {
-        let record = result?;
-        let glyph = GlyphEntry::from((&header_map, &record));
+        let record = result.map_err(tsv_error)?;
+        let glyph = GlyphEntry::try_from((&header_map, &record))?;
         data.push(glyph);
     }
-    
+
     Ok(data)
 }
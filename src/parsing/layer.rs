@@ -0,0 +1,19 @@
+use super::glyph_entries::GlyphEntry;
+
+/// The name of the default layer, per the UFO 3 spec. Its glyphs live in `UFO::glyph_entries`.
+pub const DEFAULT_LAYER_NAME: &str = "public.default";
+
+/// A layer other than the default one, as listed in layercontents.plist.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub name: String,
+    // directory relative to the UFO root, e.g. `glyphs.B_ack`
+    pub dir: String,
+    pub glyph_entries: Vec<GlyphEntry>,
+}
+
+impl Layer {
+    pub fn get(&self, glifname: &str) -> Option<&GlyphEntry> {
+        self.glyph_entries.iter().find(|e| e.glifname == glifname)
+    }
+}
@@ -1,26 +1,98 @@
+use std::path::Path;
+
+use plist::Dictionary;
 use serde_json::Value;
-use std::error::Error;
 
-#[derive(Debug, Clone)]
+use super::fontinfo::{json_to_plist, FontInfo};
+use crate::error::LoadError;
+
+// Used in place of missing metrics. Ascender and descender are fractions of the em.
+const FALLBACK_UNITS_PER_EM: f64 = 1000.;
+const FALLBACK_ASCENDER: f64 = 0.8;
+const FALLBACK_DESCENDER: f64 = -0.2;
+
+#[derive(Debug, Clone, Default)]
 pub struct Metadata {
+    // what to call the font in the UI
     pub name: String,
-    pub ascender: i32,
-    pub descender: i32,
+    pub info: FontInfo,
+    // fontinfo.plist as read, including keys `FontInfo` doesn't model
+    pub raw: Dictionary,
 }
 
-pub fn parse_metadata(output: &str) -> Result<Metadata, Box<dyn Error>> {
-    let mut lines = output.lines();
+impl Metadata {
+    pub fn from_dict(raw: Dictionary) -> Self {
+        let info = FontInfo::from_dict(&raw);
+        let name = display_name(&info);
+
+        Metadata { name, info, raw }
+    }
+
+    /// Writes `info` back to the fontinfo.plist of the UFO at `font_path`. Keys `FontInfo`
+    /// doesn't know about are written back unchanged.
+    pub fn write_fontinfo(&mut self, font_path: &Path) -> Result<(), LoadError> {
+        self.info.write_to_dict(&mut self.raw);
+        self.name = display_name(&self.info);
+
+        let path = font_path.join("fontinfo.plist");
+        plist::to_file_xml(&path, &self.raw).map_err(|e| LoadError::FontInfo {
+            path,
+            message: e.to_string(),
+        })
+    }
+
+    pub fn units_per_em(&self) -> f64 {
+        self.info.units_per_em.unwrap_or(FALLBACK_UNITS_PER_EM)
+    }
+
+    pub fn ascender(&self) -> i32 {
+        self.info
+            .ascender
+            .unwrap_or(self.units_per_em() * FALLBACK_ASCENDER)
+            .round() as i32
+    }
+
+    pub fn descender(&self) -> i32 {
+        self.info
+            .descender
+            .unwrap_or(self.units_per_em() * FALLBACK_DESCENDER)
+            .round() as i32
+    }
+}
+
+fn display_name(info: &FontInfo) -> String {
+    if let Some(name) = &info.postscript_full_name {
+        return name.clone();
+    }
+
+    match (&info.family_name, &info.style_name) {
+        (Some(family), Some(style)) => format!("{} {}", family, style),
+        (Some(family), None) => family.clone(),
+        (None, _) => info.postscript_font_name.clone().unwrap_or_else(|| "Untitled".to_string()),
+    }
+}
+
+// The keys requested from `mfekmetadata arbitrary`, in the order it prints them.
+pub const SUBPROCESS_KEYS: [&str; 4] = ["postscriptFullName", "ascender", "descender", "copyright"];
+
+/// Parses the output of `mfekmetadata arbitrary -k …` for `SUBPROCESS_KEYS`. Every line is a JSON
+/// value; keys the font doesn't have are left unset.
+pub fn parse_metadata(output: &str) -> Result<Metadata, LoadError> {
+    let malformed = |what: String| LoadError::Subprocess(format!("malformed metadata: {}", what));
+    let mut raw = Dictionary::new();
 
-    let name = lines.next().ok_or("Missing name")?;
-    let name: Value = serde_json::from_str(name)?;
-    let name = name.as_str().unwrap_or("").to_string();
+    for (key, line) in SUBPROCESS_KEYS.iter().zip(output.lines()) {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
 
-    let ascender = lines.next().ok_or("Missing ascender")?.parse::<i32>()?;
-    let descender = lines.next().ok_or("Missing descender")?.parse::<i32>()?;
+        let value: Value =
+            serde_json::from_str(line).map_err(|e| malformed(format!("{}: {}", key, e)))?;
+        if let Some(value) = json_to_plist(&value) {
+            raw.insert(key.to_string(), value);
+        }
+    }
 
-    Ok(Metadata {
-        name,
-        ascender,
-        descender,
-    })
+    Ok(Metadata::from_dict(raw))
 }
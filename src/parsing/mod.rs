@@ -1,2 +1,4 @@
+pub mod fontinfo;
 pub mod glyph_entries;
+pub mod layer;
 pub mod metadata;
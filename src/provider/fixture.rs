@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::MetadataProvider;
+use crate::error::LoadError;
+use crate::parsing::{glyph_entries::GlyphEntry, metadata::Metadata};
+
+/// Serves glyphs and metadata held in memory, keyed by font path. Nothing is read from disk, so
+/// swapped in with `UFOViewer::set_provider` it opens fonts put together in code, e.g. in tests on
+/// machines without mfekmetadata.
+// nothing but the tests swaps it in yet
+#[allow(dead_code)]
+#[derive(Debug, Default, Clone)]
+pub struct FixtureProvider {
+    fonts: HashMap<PathBuf, (Vec<GlyphEntry>, Metadata)>,
+}
+
+#[allow(dead_code)]
+impl FixtureProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves `glyph_entries` and `metadata` for the font at `font_path`, which needn't exist.
+    pub fn insert(
+        &mut self,
+        font_path: PathBuf,
+        mut glyph_entries: Vec<GlyphEntry>,
+        metadata: Metadata,
+    ) {
+        glyph_entries.sort_by(|a, b| a.codepoints.cmp(&b.codepoints));
+        self.fonts.insert(font_path, (glyph_entries, metadata));
+    }
+
+    fn get(&self, font_path: &Path) -> Result<&(Vec<GlyphEntry>, Metadata), LoadError> {
+        self.fonts
+            .get(font_path)
+            .ok_or_else(|| LoadError::Font {
+                path: font_path.to_owned(),
+                message: "no fixture for this font".to_string(),
+            })
+    }
+}
+
+impl MetadataProvider for FixtureProvider {
+    fn glyph_entries(&self, font_path: &Path) -> Result<Vec<GlyphEntry>, LoadError> {
+        Ok(self.get(font_path)?.0.clone())
+    }
+
+    fn metadata(&self, font_path: &Path) -> Result<Metadata, LoadError> {
+        Ok(self.get(font_path)?.1.clone())
+    }
+}
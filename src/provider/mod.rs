@@ -0,0 +1,157 @@
+use std::path::Path;
+
+use crate::error::LoadError;
+use crate::parsing::{glyph_entries::GlyphEntry, layer::Layer, metadata::Metadata};
+
+mod fixture;
+mod native;
+mod subprocess;
+
+pub use fixture::FixtureProvider;
+pub use native::NativeProvider;
+pub use subprocess::SubprocessProvider;
+
+/// A source of glyph lists and font-level metadata for a UFO on disk.
+///
+/// `UFOViewer::load_ufo_from_path` goes through one of these, so the viewer doesn't care whether
+/// the data came from mfekmetadata, straight from disk, or from memory.
+///
+/// Providers are shared with the background loader, hence `Send + Sync`.
+pub trait MetadataProvider: Send + Sync {
+    /// Glyphs of the default layer, sorted by codepoint.
+    fn glyph_entries(&self, font_path: &Path) -> Result<Vec<GlyphEntry>, LoadError>;
+    fn metadata(&self, font_path: &Path) -> Result<Metadata, LoadError>;
+
+    /// Like `glyph_entries`, but hands glyphs to `on_batch` as they are parsed, along with the
+    /// total number of glyphs in the font. Batches are not sorted relative to each other.
+    ///
+    /// The default implementation delivers everything in a single batch.
+    fn stream_glyph_entries(
+        &self,
+        font_path: &Path,
+        on_batch: &mut dyn FnMut(Vec<GlyphEntry>, usize),
+    ) -> Result<(), LoadError> {
+        let entries = self.glyph_entries(font_path)?;
+        let total = entries.len();
+        on_batch(entries, total);
+        Ok(())
+    }
+
+    /// Every layer but the default one, in layercontents.plist order. Providers that don't know
+    /// about layers report none.
+    fn layers(&self, _font_path: &Path) -> Result<Vec<Layer>, LoadError> {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use glifparser::Glif;
+    use plist::{Dictionary, Value};
+
+    use super::*;
+
+    fn kjv() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("test_data/KJV1611.ufo")
+    }
+
+    fn glifnames(entries: &[GlyphEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.glifname.as_str()).collect()
+    }
+
+    #[test]
+    fn native_glyph_entries() {
+        let entries = NativeProvider.glyph_entries(&kjv()).unwrap();
+        assert_eq!(entries.len(), 694);
+        assert!(entries.iter().all(|e| !e.is_broken()));
+        assert!(entries.windows(2).all(|w| w[0].codepoints <= w[1].codepoints));
+
+        let a = entries.iter().find(|e| e.glifname == "A").unwrap();
+        assert_eq!(a.codepoints, "0041");
+        assert_eq!(a.uniname, "LATIN CAPITAL LETTER A");
+        assert_eq!(a.unicat, "Lu");
+        assert!(a.filename.ends_with("glyphs/A_.glif"));
+    }
+
+    #[test]
+    fn native_metadata() {
+        let metadata = NativeProvider.metadata(&kjv()).unwrap();
+        assert_eq!(metadata.name, "KJV 1611");
+        assert_eq!(metadata.info.family_name.as_deref(), Some("KJV1611"));
+        assert_eq!(metadata.info.style_name.as_deref(), Some("Regular"));
+        assert_eq!(metadata.units_per_em(), 1000.);
+        assert_eq!(metadata.ascender(), 828);
+        assert_eq!(metadata.descender(), -172);
+        let guidelines = metadata.info.guidelines.unwrap();
+        assert_eq!(guidelines[0].name.as_deref(), Some("comb. e"));
+        assert_eq!(guidelines[0].y, Some(951.666666667));
+    }
+
+    #[test]
+    fn native_layers() {
+        let layers = NativeProvider.layers(&kjv()).unwrap();
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].name, "Back");
+        assert_eq!(layers[0].dir, "glyphs.B_ack");
+        assert_eq!(layers[0].glyph_entries.len(), 12);
+    }
+
+    fn fixture_font() -> (PathBuf, FixtureProvider) {
+        let entry = |glifname: &str, codepoints: &str| GlyphEntry {
+            glifname: glifname.to_string(),
+            codepoints: codepoints.to_string(),
+            uniname: String::new(),
+            unicat: String::new(),
+            filename: format!("/nonexistent/Fixture.ufo/glyphs/{}.glif", glifname),
+            glif: Glif::new(),
+            layered: None,
+            error: None,
+        };
+        let mut raw = Dictionary::new();
+        raw.insert("familyName".to_string(), "Fixture".into());
+        raw.insert("styleName".to_string(), "Bold".into());
+        raw.insert("unitsPerEm".to_string(), Value::Integer(2048.into()));
+
+        let path = PathBuf::from("/nonexistent/Fixture.ufo");
+        let mut fixture = FixtureProvider::new();
+        fixture.insert(
+            path.clone(),
+            vec![entry("B", "0042"), entry("space", "0020"), entry("A", "0041")],
+            Metadata::from_dict(raw),
+        );
+        (path, fixture)
+    }
+
+    #[test]
+    fn fixture_serves_fonts_from_memory() {
+        let (path, fixture) = fixture_font();
+
+        let entries = fixture.glyph_entries(&path).unwrap();
+        assert_eq!(glifnames(&entries), ["space", "A", "B"]);
+
+        let metadata = fixture.metadata(&path).unwrap();
+        assert_eq!(metadata.name, "Fixture Bold");
+        assert_eq!(metadata.units_per_em(), 2048.);
+        assert!(fixture.layers(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn fixture_streams_one_batch() {
+        let (path, fixture) = fixture_font();
+
+        let mut batches = Vec::new();
+        fixture
+            .stream_glyph_entries(&path, &mut |batch, total| batches.push((batch.len(), total)))
+            .unwrap();
+        assert_eq!(batches, vec![(3, 3)]);
+    }
+
+    #[test]
+    fn fixture_without_font() {
+        let fixture = FixtureProvider::new();
+        assert!(matches!(fixture.glyph_entries(&kjv()), Err(LoadError::Font { .. })));
+        assert!(matches!(fixture.metadata(&kjv()), Err(LoadError::Font { .. })));
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use norad::{DataRequest, Font};
+use unic_char_property::EnumeratedCharProperty;
+use unic_ucd_category::GeneralCategory;
+
+use super::MetadataProvider;
+use crate::error::LoadError;
+use crate::parsing::{
+    glyph_entries::{is_glifjson, GlyphEntry},
+    layer::{Layer, DEFAULT_LAYER_NAME},
+    metadata::Metadata,
+};
+
+/// Reads fontinfo in-process with norad, and glyphs with plist and glifparser.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NativeProvider;
+
+impl MetadataProvider for NativeProvider {
+    fn glyph_entries(&self, font_path: &Path) -> Result<Vec<GlyphEntry>, LoadError> {
+        load_glyph_entries(font_path)
+    }
+
+    fn metadata(&self, font_path: &Path) -> Result<Metadata, LoadError> {
+        load_metadata(font_path)
+    }
+
+    fn stream_glyph_entries(
+        &self,
+        font_path: &Path,
+        on_batch: &mut dyn FnMut(Vec<GlyphEntry>, usize),
+    ) -> Result<(), LoadError> {
+        stream_glyph_entries(font_path, on_batch)
+    }
+
+    fn layers(&self, font_path: &Path) -> Result<Vec<Layer>, LoadError> {
+        load_layers(font_path)
+    }
+}
+
+// How many glyphs are parsed between progress reports when streaming.
+const BATCH_SIZE: usize = 64;
+
+// Reads the glyph list of the default layer straight from the UFO, without going through
+// mfekmetadata. The columns mirror those of `mfekmetadata glyphs`.
+fn load_glyph_entries(font_path: &Path) -> Result<Vec<GlyphEntry>, LoadError> {
+    load_layer_entries(&font_path.join("glyphs"))
+}
+
+fn load_layer_entries(glyphs_dir: &Path) -> Result<Vec<GlyphEntry>, LoadError> {
+    let mut data: Vec<GlyphEntry> = list_glyphs(glyphs_dir)?
+        .into_iter()
+        .map(|(glifname, filename)| load_glyph_entry(glifname, filename))
+        .collect();
+
+    data.sort_by(|a, b| a.codepoints.cmp(&b.codepoints));
+    Ok(data)
+}
+
+fn stream_glyph_entries(
+    font_path: &Path,
+    on_batch: &mut dyn FnMut(Vec<GlyphEntry>, usize),
+) -> Result<(), LoadError> {
+    let glyphs = list_glyphs(&font_path.join("glyphs"))?;
+    let total = glyphs.len();
+
+    for chunk in glyphs.chunks(BATCH_SIZE) {
+        let batch = chunk
+            .iter()
+            .cloned()
+            .map(|(glifname, filename)| load_glyph_entry(glifname, filename))
+            .collect();
+        on_batch(batch, total);
+    }
+
+    Ok(())
+}
+
+// Returns (glifname, glyph path) pairs from a layer's contents.plist, followed by any .glifjson
+// files in the layer's directory that contents.plist doesn't list. MFEKglif doesn't add layered
+// glyphs to contents.plist, so a layer made only of them may not have one at all.
+fn list_glyphs(glyphs_dir: &Path) -> Result<Vec<(String, String)>, LoadError> {
+    let contents_path = glyphs_dir.join("contents.plist");
+    let layered = list_glifjsons(glyphs_dir);
+
+    let contents: BTreeMap<String, String> = if !contents_path.exists() && !layered.is_empty() {
+        BTreeMap::new()
+    } else {
+        plist::from_file(&contents_path).map_err(|e| LoadError::Contents {
+            path: contents_path.clone(),
+            message: e.to_string(),
+        })?
+    };
+
+    let listed: HashSet<PathBuf> = contents.values().map(|f| glyphs_dir.join(f)).collect();
+    let unlisted = layered
+        .into_iter()
+        .filter(|path| !listed.contains(path))
+        .map(|path| (glifjson_name(&path), path));
+
+    contents
+        .into_iter()
+        .map(|(glifname, glif_filename)| (glifname, glyphs_dir.join(glif_filename)))
+        .chain(unlisted)
+        .map(|(glifname, filename)| {
+            let filename = filename
+                .to_str()
+                .ok_or_else(|| LoadError::Glif {
+                    path: filename.clone(),
+                    message: "path is not valid UTF-8".to_string(),
+                })?
+                .to_string();
+            Ok((glifname, filename))
+        })
+        .collect()
+}
+
+fn list_glifjsons(glyphs_dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(glyphs_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_glifjson(path))
+        .collect();
+    paths.sort();
+    paths
+}
+
+// A stand-in name taken from the file name, e.g. `S_.rhigh.layered.glifjson` gives `S_.rhigh`.
+// `GlyphEntry::load` replaces it with the name stored in the file.
+fn glifjson_name(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    stem.strip_suffix(".layered").unwrap_or(&stem).to_string()
+}
+
+// layercontents.plist is an array of [layer name, directory] pairs. UFO 2 fonts don't have one,
+// and only have the default layer.
+fn load_layers(font_path: &Path) -> Result<Vec<Layer>, LoadError> {
+    let layercontents_path = font_path.join("layercontents.plist");
+    if !layercontents_path.is_file() {
+        return Ok(Vec::new());
+    }
+
+    let layercontents: Vec<(String, String)> =
+        plist::from_file(&layercontents_path).map_err(|e| LoadError::Contents {
+            path: layercontents_path.clone(),
+            message: e.to_string(),
+        })?;
+
+    layercontents
+        .into_iter()
+        .filter(|(name, dir)| name != DEFAULT_LAYER_NAME && dir != "glyphs")
+        .map(|(name, dir)| {
+            let glyph_entries = load_layer_entries(&font_path.join(&dir))?;
+            Ok(Layer {
+                name,
+                dir,
+                glyph_entries,
+            })
+        })
+        .collect()
+}
+
+fn load_glyph_entry(glifname: String, filename: String) -> GlyphEntry {
+    let mut entry = GlyphEntry::load(glifname, String::new(), String::new(), String::new(), filename);
+
+    entry.codepoints = entry
+        .glif
+        .unicode
+        .iter()
+        .map(|c| format!("{:04X}", *c as u32))
+        .collect::<Vec<_>>()
+        .join(",");
+    if let Some(c) = entry.glif.unicode.first() {
+        entry.uniname = unicode_names2::name(*c).map(|n| n.to_string()).unwrap_or_default();
+        entry.unicat = GeneralCategory::of(*c).abbr_name().to_string();
+    }
+
+    entry
+}
+
+// norad checks fontinfo.plist against the spec and brings the keys of UFO 1 and 2 fonts up to
+// UFO 3. Glyphs are left to glifparser, so only fontinfo is requested.
+fn load_metadata(font_path: &Path) -> Result<Metadata, LoadError> {
+    let fontinfo_path = font_path.join("fontinfo.plist");
+    let fontinfo_error = |message: String| LoadError::FontInfo {
+        path: fontinfo_path.clone(),
+        message,
+    };
+
+    let raw = match Font::load_requested_data(font_path, DataRequest::none()) {
+        Ok(font) => fontinfo_to_dict(&font.font_info).map_err(fontinfo_error)?,
+        // norad gives up on the whole font over one bad value or unknown key; such fonts are read
+        // as they are, so that they can still be opened and fixed in the Font Info tab
+        Err(e) => {
+            log::warn!("norad can't read {}: {}", font_path.display(), e);
+            read_fontinfo(&fontinfo_path).map_err(fontinfo_error)?
+        }
+    };
+
+    Ok(Metadata::from_dict(raw))
+}
+
+fn fontinfo_to_dict(info: &norad::FontInfo) -> Result<plist::Dictionary, String> {
+    let mut xml = Vec::new();
+    plist::to_writer_xml(&mut xml, info).map_err(|e| e.to_string())?;
+    plist::from_bytes(&xml).map_err(|e| e.to_string())
+}
+
+// fontinfo.plist is optional, a font without one just has no metadata
+fn read_fontinfo(fontinfo_path: &Path) -> Result<plist::Dictionary, String> {
+    if !fontinfo_path.is_file() {
+        return Ok(plist::Dictionary::new());
+    }
+    plist::from_file(fontinfo_path).map_err(|e| e.to_string())
+}
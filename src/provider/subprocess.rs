@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str;
+
+use mfek_ipc::module::available;
+
+use super::MetadataProvider;
+use crate::error::LoadError;
+use crate::parsing::{
+    glyph_entries::{parse_tsv, GlyphEntry},
+    metadata::{parse_metadata, Metadata, SUBPROCESS_KEYS},
+};
+
+/// Asks an installed mfekmetadata for everything, parsing its TSV and line-based output.
+#[derive(Debug, Clone)]
+pub struct SubprocessProvider {
+    metadata_path: PathBuf,
+}
+
+impl SubprocessProvider {
+    pub fn new(metadata_path: PathBuf) -> Self {
+        SubprocessProvider { metadata_path }
+    }
+
+    /// Finds mfekmetadata the same way the other MFEK modules do. Returns `None` if it isn't
+    /// installed.
+    pub fn locate() -> Option<Self> {
+        let (v, pbuf) = available("metadata", "0.0.4").ok()?;
+        if let mfek_ipc::module::Version::OutOfDate(_) = v {
+            log::warn!("Version mismatch found with mfekmetadata!")
+        }
+
+        Some(Self::new(pbuf))
+    }
+
+    fn run(&self, args: &[&str]) -> Result<String, LoadError> {
+        let output = Command::new(&self.metadata_path)
+            .args(args)
+            .output()
+            .map_err(|e| LoadError::Subprocess(format!("failed to run: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(LoadError::Subprocess(format!(
+                "exited with a non-zero exit code: {:?}\n{}",
+                output.status.code(),
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+
+        str::from_utf8(&output.stdout)
+            .map(str::to_owned)
+            .map_err(|e| LoadError::Subprocess(format!("output was not valid UTF-8: {}", e)))
+    }
+}
+
+fn utf8_path(font_path: &Path) -> Result<&str, LoadError> {
+    font_path.to_str().ok_or_else(|| LoadError::Font {
+        path: font_path.to_owned(),
+        message: "path is not valid UTF-8".to_string(),
+    })
+}
+
+impl MetadataProvider for SubprocessProvider {
+    fn glyph_entries(&self, font_path: &Path) -> Result<Vec<GlyphEntry>, LoadError> {
+        let font_path = utf8_path(font_path)?;
+        let stdout_str = self.run(&[font_path, "glyphs"])?;
+
+        let mut data = parse_tsv(&stdout_str)?;
+        data.sort_by(|a, b| a.codepoints.cmp(&b.codepoints));
+        Ok(data)
+    }
+
+    fn metadata(&self, font_path: &Path) -> Result<Metadata, LoadError> {
+        let font_path = utf8_path(font_path)?;
+        let mut args = vec![font_path, "arbitrary"];
+        for key in SUBPROCESS_KEYS {
+            args.extend(["-k", key]);
+        }
+        let stdout_str = self.run(&args)?;
+
+        parse_metadata(&stdout_str)
+    }
+}
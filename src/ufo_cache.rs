@@ -6,32 +6,120 @@ use std::{
 use egui::{Context, TextureHandle};
 use glifparser::{FlattenedGlif, Glif, MFEKGlif};
 use glifrenderer::{glyph::Style, toggles::PreviewMode, viewport::Viewport};
-use skia_safe::{Color, Color4f, Font, Paint, Point, Surface, TextBlob, Typeface};
+use skia_safe::{paint, Color, Color4f, Font, Paint, Point, Surface, TextBlob, Typeface};
 
 use crate::{interpolation, parsing::{glyph_entries::GlyphEntry, metadata::Metadata}};
 
+// A glyph and the glyph from the background layer drawn behind it, if any. The same glyph gets
+// a separate texture for every background it's shown over.
+type CacheKey = (GlyphEntry, Option<GlyphEntry>);
+
+// A thumbnail of the Instance tab waiting to be drawn. `base` is the base master's glyph; it's
+// only interpolated once the request is taken off the queue.
+struct InstanceRequest {
+    base: GlyphEntry,
+    generation: u64,
+}
+
+// One glyph of every master that has it, to be drawn over each other.
+struct OverlayRequest {
+    glifname: String,
+    entries: Vec<GlyphEntry>,
+}
+
+// Size of the overlay image, which is shown larger than a grid cell.
+const OVERLAY_SIZE: f32 = 256.;
+
+// Each master's color in the overlay, in master order.
+const OVERLAY_COLORS: [u32; 6] = [
+    0xffe6194b, 0xff3cb44b, 0xff4363d8, 0xfff58231, 0xff911eb4, 0xff42d4f4,
+];
+
+/// The overlay color of the `idx`th master.
+pub fn overlay_color(idx: usize) -> egui::Color32 {
+    let [b, g, r, _] = OVERLAY_COLORS[idx % OVERLAY_COLORS.len()].to_le_bytes();
+    egui::Color32::from_rgb(r, g, b)
+}
+
 #[derive(Default)]
 pub struct UFOCache {
     default_texture: Option<TextureHandle>,
-    texture_handles: HashMap<GlyphEntry, TextureHandle>,
-    needs_rebuild: VecDeque<GlyphEntry>,
+    texture_handles: HashMap<CacheKey, TextureHandle>,
+    needs_rebuild: VecDeque<CacheKey>,
+    // Instance tab thumbnails by glifname, with the preview generation each was drawn for
+    instance_textures: HashMap<String, (u64, TextureHandle)>,
+    needs_instance_rebuild: VecDeque<InstanceRequest>,
+    // overlays by glifname, with the entries each was drawn from
+    overlay_textures: HashMap<String, (Vec<GlyphEntry>, TextureHandle)>,
+    needs_overlay_rebuild: VecDeque<OverlayRequest>,
 }
 
 impl UFOCache {
     pub fn get_image_handle(
         &mut self,
         glyph_entry: &GlyphEntry,
+        background: Option<&GlyphEntry>,
     ) -> &TextureHandle {
-        let texture_handle = self.texture_handles.get(glyph_entry);
+        let key = (glyph_entry.clone(), background.cloned());
 
-        if let Some(txhandle) = texture_handle {
-            return txhandle;
+        if self.texture_handles.contains_key(&key) {
+            return &self.texture_handles[&key];
         } else {
-            self.needs_rebuild.push_front(glyph_entry.clone());
+            self.needs_rebuild.push_front(key);
             return self.default_texture.as_ref().unwrap();
         }
     }
 
+    /// The Instance tab thumbnail of `base`'s glyph. While the preview location is changing, the
+    /// thumbnail of an earlier `generation` is shown until the new one has been drawn. The glyph
+    /// is interpolated by `rebuild_images` when it gets to drawing it.
+    pub fn get_instance_image_handle(
+        &mut self,
+        base: &GlyphEntry,
+        generation: u64,
+    ) -> &TextureHandle {
+        let up_to_date = matches!(
+            self.instance_textures.get(&base.glifname),
+            Some((drawn, _)) if *drawn == generation
+        );
+        if !up_to_date {
+            self.needs_instance_rebuild.push_front(InstanceRequest {
+                base: base.clone(),
+                generation,
+            });
+        }
+
+        match self.instance_textures.get(&base.glifname) {
+            Some((_, texture)) => texture,
+            None => self.default_texture.as_ref().unwrap(),
+        }
+    }
+
+    /// `entries`, the same glyph from each master that has it, drawn over each other in the
+    /// masters' `overlay_color`s, with lines joining the points interpolation pairs up. Until the
+    /// overlay has been drawn for these exact entries, an older one is shown if there is one.
+    pub fn get_overlay_image_handle(
+        &mut self,
+        glifname: &str,
+        entries: Vec<GlyphEntry>,
+    ) -> &TextureHandle {
+        let up_to_date = matches!(
+            self.overlay_textures.get(glifname),
+            Some((drawn, _)) if *drawn == entries
+        );
+        if !up_to_date {
+            self.needs_overlay_rebuild.push_front(OverlayRequest {
+                glifname: glifname.to_owned(),
+                entries,
+            });
+        }
+
+        match self.overlay_textures.get(glifname) {
+            Some((_, texture)) => texture,
+            None => self.default_texture.as_ref().unwrap(),
+        }
+    }
+
     pub fn create_default_texture(&mut self, ctx: &Context) {
         if self.default_texture.is_some() {
             return;
@@ -50,23 +138,67 @@ impl UFOCache {
             self.texture_handles.remove(&entry);
             self.needs_rebuild.push_front(entry);
         }
+        self.instance_textures.clear();
+        self.overlay_textures.clear();
+    }
+
+    /// Drops every thumbnail of glyph `glifname`, on its own or as a background, so that they're
+    /// drawn again the next time they're shown. Cheaper than `force_rebuild_all` when a single
+    /// glyph changed.
+    pub fn rebuild_glyph(&mut self, glifname: &str) {
+        self.texture_handles.retain(|(entry, background), _| {
+            entry.glifname != glifname
+                && background.as_ref().map_or(true, |b| b.glifname != glifname)
+        });
+        self.needs_rebuild.retain(|(entry, _)| entry.glifname != glifname);
+        self.instance_textures.remove(glifname);
+        self.overlay_textures.remove(glifname);
     }
 
-    pub fn rebuild_images(&mut self, ctx: &Context, metadata: &Metadata, interp_check: &Option<interpolation::InterpolationCheckResults>) {
+    /// Draws queued thumbnails for up to a frame's worth of time. `interpolate` blends a glyph at
+    /// the Instance tab's current location; without it, queued instance thumbnails are dropped.
+    pub fn rebuild_images(
+        &mut self,
+        ctx: &Context,
+        metadata: &Metadata,
+        interp_check: &Option<interpolation::InterpolationCheckResults>,
+        interpolate: Option<&dyn Fn(&str) -> Option<Glif<()>>>,
+    ) {
         let time_limit = 1. / 30.;
         let start_time = Instant::now();
 
         while start_time.elapsed().as_secs_f32() < time_limit {
-            let to_rebuild = self.needs_rebuild.pop_back();
-            if let Some(entry) = to_rebuild {
+            if let Some((entry, background)) = self.needs_rebuild.pop_back() {
                 let mut interp_success = true;
 
                 if let Some(interp_info) = interp_check {
-                    if interp_info.combined.get(&entry.uniname).is_some() {
+                    if interp_info.combined.get(&entry.glifname).is_some() {
                         interp_success = false;
                     }
                 }
-                self.generate_image_handle(ctx, &entry, metadata, interp_success)
+                self.generate_image_handle(ctx, &entry, background.as_ref(), metadata, interp_success)
+            } else if let Some(request) = self.needs_instance_rebuild.pop_back() {
+                let interpolate = match interpolate {
+                    Some(interpolate) => interpolate,
+                    None => continue,
+                };
+                let mut entry = request.base;
+                // the interpolated outline replaces any layers
+                entry.layered = None;
+                let interpolated = match interpolate(&entry.glifname) {
+                    Some(glif) => {
+                        entry.glif = glif;
+                        true
+                    }
+                    None => false,
+                };
+                let texture = self.render_texture(ctx, &entry, None, metadata, interpolated);
+                self.instance_textures
+                    .insert(entry.glifname, (request.generation, texture));
+            } else if let Some(request) = self.needs_overlay_rebuild.pop_back() {
+                let texture = self.render_overlay(ctx, &request, metadata);
+                self.overlay_textures
+                    .insert(request.glifname, (request.entries, texture));
             } else {
                 break;
             }
@@ -75,30 +207,43 @@ impl UFOCache {
 
     pub fn clear_rebuild(&mut self) {
         self.needs_rebuild = VecDeque::new();
+        self.needs_instance_rebuild = VecDeque::new();
+        self.needs_overlay_rebuild = VecDeque::new();
     }
 
     fn generate_image_handle(
         &mut self,
         ctx: &Context,
         glyph_entry: &GlyphEntry,
+        background: Option<&GlyphEntry>,
         metadata: &Metadata,
         interp_success: bool,
     ) {
-        if self.texture_handles.contains_key(glyph_entry) {
+        let key = (glyph_entry.clone(), background.cloned());
+        if self.texture_handles.contains_key(&key) {
             return;
         }
 
-        let mut glif: Glif<()> = glyph_entry.glif.clone();
-        if glif.components.vec.len() > 0 {
-            glif = glif.flattened(&mut None).unwrap_or(glif);
-        }
+        let texture_handle =
+            self.render_texture(ctx, glyph_entry, background, metadata, interp_success);
+        self.texture_handles.insert(key, texture_handle);
+    }
 
-        let glif_name = glif.name.clone();
-        let mfekglif: MFEKGlif<()> = MFEKGlif::from(glif);
+    fn render_texture(
+        &mut self,
+        ctx: &Context,
+        glyph_entry: &GlyphEntry,
+        background: Option<&GlyphEntry>,
+        metadata: &Metadata,
+        interp_success: bool,
+    ) -> TextureHandle {
+        let glif_name = glyph_entry.glif.name.clone();
+        let mfekglif = Self::flattened_mfekglif(glyph_entry);
+        let background = background.map(Self::flattened_mfekglif);
 
         // create the viewport
-        let ascender = metadata.ascender;
-        let descender = metadata.descender;
+        let ascender = metadata.ascender();
+        let descender = metadata.descender();
         let mut viewport =
             UFOCache::create_viewport_for_glyph_centered(&mfekglif, ascender, descender);
 
@@ -106,14 +251,106 @@ impl UFOCache {
             ctx.style().visuals.text_color().to_array().into(),
         ));
 
-        let (size, image_data) =
-            self.create_canvas_and_get_image_data(&mfekglif, &mut viewport, egui_text_color, interp_success);
+        let (size, image_data) = self.create_canvas_and_get_image_data(
+            &mfekglif,
+            background.as_ref(),
+            &mut viewport,
+            egui_text_color,
+            interp_success,
+            glyph_entry.is_broken(),
+        );
         let egui_image = egui::ColorImage::from_rgba_unmultiplied([size, size], &image_data);
 
-        let texture_handle = ctx.load_texture(glif_name, egui_image, Default::default());
+        ctx.load_texture(glif_name, egui_image, Default::default())
+    }
 
-        self.texture_handles
-            .insert(glyph_entry.clone(), texture_handle);
+    fn render_overlay(
+        &mut self,
+        ctx: &Context,
+        request: &OverlayRequest,
+        metadata: &Metadata,
+    ) -> TextureHandle {
+        let glyphs: Vec<MFEKGlif<()>> =
+            request.entries.iter().map(Self::flattened_mfekglif).collect();
+        let dimension = OVERLAY_SIZE as usize;
+
+        let mut surface =
+            Surface::new_raster_n32_premul((dimension as i32, dimension as i32)).unwrap();
+        let canvas = surface.canvas();
+        canvas.clear(Color4f::new(0., 0., 0., 0.));
+
+        if let Some(first) = glyphs.first() {
+            let mut viewport = Self::viewport_centered(
+                first,
+                metadata.ascender(),
+                metadata.descender(),
+                OVERLAY_SIZE,
+            );
+            viewport.redraw(canvas);
+
+            // every outline faded, so that they show through each other
+            for (i, glyph) in glyphs.iter().enumerate() {
+                let color = Color::new(OVERLAY_COLORS[i % OVERLAY_COLORS.len()]).with_a(0x60);
+                let style = Style::new(Color::new(0xffffffff), color.into());
+                glifrenderer::glyph::draw(canvas, glyph, &mut viewport, Some(style));
+            }
+
+            // lines from each master's points to the next master's, and each contour's start
+            let text_color: Color = Color::new(u32::from_le_bytes(
+                ctx.style().visuals.text_color().to_array().into(),
+            ));
+            let mut line = Paint::new(Color4f::from(text_color.with_a(0xa0)), None);
+            line.set_anti_alias(true);
+            line.set_style(paint::Style::Stroke);
+            line.set_stroke_width(1. / viewport.factor);
+
+            let outlines: Vec<_> = request
+                .entries
+                .iter()
+                .map(|e| e.glif.outline.clone().unwrap_or_default())
+                .collect();
+            for pair in outlines.windows(2) {
+                for (from, to) in pair[0].iter().zip(&pair[1]) {
+                    for (p, q) in from.iter().zip(to) {
+                        canvas.draw_line((p.x, -p.y), (q.x, -q.y), &line);
+                    }
+                }
+            }
+
+            for (i, outline) in outlines.iter().enumerate() {
+                let color = Color::new(OVERLAY_COLORS[i % OVERLAY_COLORS.len()]);
+                let mut start = Paint::new(Color4f::from(color), None);
+                start.set_anti_alias(true);
+                for contour in outline {
+                    if let Some(p) = contour.first() {
+                        canvas.draw_circle((p.x, -p.y), 4. / viewport.factor, &start);
+                    }
+                }
+            }
+        }
+
+        let image_info = surface.image_info();
+        let row_bytes = image_info.min_row_bytes();
+        let mut image_data = vec![0u8; row_bytes * dimension];
+        let success = surface.read_pixels(&image_info, &mut image_data, row_bytes, (0, 0));
+        assert!(success, "Failed to read pixels from the Surface");
+
+        let egui_image =
+            egui::ColorImage::from_rgba_unmultiplied([dimension, dimension], &image_data);
+        ctx.load_texture(format!("overlay {}", request.glifname), egui_image, Default::default())
+    }
+
+    fn flattened_mfekglif(glyph_entry: &GlyphEntry) -> MFEKGlif<()> {
+        // glifrenderer composites the layers of a .glifjson itself, layer operations included
+        if let Some(layered) = &glyph_entry.layered {
+            return layered.clone();
+        }
+
+        let mut glif: Glif<()> = glyph_entry.glif.clone();
+        if glif.components.vec.len() > 0 {
+            glif = glif.flattened(&mut None).unwrap_or(glif);
+        }
+        MFEKGlif::from(glif)
     }
 
     pub fn create_viewport_for_glyph_centered(
@@ -121,7 +358,15 @@ impl UFOCache {
         ascender: i32,
         descender: i32,
     ) -> Viewport {
-        let canvas_size = 128.0;
+        Self::viewport_centered(glyph, ascender, descender, 128.0)
+    }
+
+    fn viewport_centered(
+        glyph: &MFEKGlif<()>,
+        ascender: i32,
+        descender: i32,
+        canvas_size: f32,
+    ) -> Viewport {
         let factor = canvas_size / (ascender - descender + 12) as f32 * 0.6;
         let glyph_width = glyph.width.unwrap_or(0);
         let x_offset = glyph_width as f32 / 2.0;
@@ -168,15 +413,17 @@ impl UFOCache {
     fn create_canvas_and_get_image_data(
         &mut self,
         mfekglif: &MFEKGlif<()>,
+        background: Option<&MFEKGlif<()>>,
         viewport: &mut Viewport,
         text_color: Color,
-        interp_success: bool
+        interp_success: bool,
+        broken: bool,
     ) -> (usize, Vec<u8>) {
         let dimension: usize = 128;
 
-        // Draw the Glyph name
+        // Draw the Glyph name, in red if the .glif failed to load
         let mut paint = Paint::new(Color4f::new(1., 1., 1., 1.), None);
-        paint.set_color(text_color);
+        paint.set_color(if broken { Color::RED } else { text_color });
         let typeface: skia_safe::RCHandle<skia_bindings::SkTypeface> = Typeface::default();
         let font = Font::new(typeface, 12.0); // Adjust the font size here
         let text_blob = TextBlob::new(&mfekglif.name, &font).unwrap();
@@ -216,9 +463,17 @@ impl UFOCache {
             canvas.draw_text_blob(&text_blob, text_position, &paint);
         }
 
+        viewport.redraw(canvas);
+
+        // Draw the background layer's glyph faded out behind the glyph
+        if let Some(background) = background {
+            let faded = text_color.with_a(0x50);
+            let style = Style::new(Color::new(0xffffffff), faded.into());
+            glifrenderer::glyph::draw(canvas, background, viewport, Some(style));
+        }
+
         // Draw the glyph
         let style = Style::new(Color::new(0xffffffff), text_color.into());
-        viewport.redraw(canvas);
         glifrenderer::glyph::draw(canvas, mfekglif, viewport, Some(style));
 
         // Get the ImageInfo from the Surface
@@ -0,0 +1,120 @@
+use std::{
+    ffi::OsStr,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use tempfile::TempDir;
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::error::LoadError;
+
+/// A zipped UFO (.ufoz), unpacked into a temporary directory for as long as it's open. Everything
+/// else in MFEKufo (and MFEKglif) works on the unpacked copy; `save` zips it back up.
+pub struct UfozArchive {
+    pub archive_path: PathBuf,
+    // the .ufo directory inside `tempdir`
+    pub ufo_path: PathBuf,
+    tempdir: TempDir,
+}
+
+pub fn is_ufoz<P: AsRef<Path>>(path: P) -> bool {
+    let path = path.as_ref();
+    path.is_file()
+        && matches!(
+            path.extension().and_then(OsStr::to_str),
+            Some("ufoz") | Some("zip")
+        )
+}
+
+impl UfozArchive {
+    pub fn open(archive_path: &Path) -> Result<Self, LoadError> {
+        let archive_error = |message: String| LoadError::Archive {
+            path: archive_path.to_owned(),
+            message,
+        };
+
+        let file = File::open(archive_path).map_err(|e| archive_error(e.to_string()))?;
+        let mut archive = ZipArchive::new(file).map_err(|e| archive_error(e.to_string()))?;
+        let tempdir = tempfile::Builder::new()
+            .prefix("MFEKufo-")
+            .tempdir()
+            .map_err(|e| archive_error(e.to_string()))?;
+        archive
+            .extract(tempdir.path())
+            .map_err(|e| archive_error(e.to_string()))?;
+
+        let ufo_path = find_ufo_root(tempdir.path())
+            .ok_or_else(|| archive_error("no metainfo.plist found in archive".to_string()))?;
+
+        Ok(UfozArchive {
+            archive_path: archive_path.to_owned(),
+            ufo_path,
+            tempdir,
+        })
+    }
+
+    /// Writes the unpacked UFO back over the original archive. The new archive is written next to
+    /// the old one first, so a failed save never leaves a truncated file behind.
+    pub fn save(&self) -> Result<(), LoadError> {
+        let archive_error = |e: &dyn ToString| LoadError::Archive {
+            path: self.archive_path.clone(),
+            message: e.to_string(),
+        };
+
+        let mut partial = self.archive_path.clone().into_os_string();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+
+        let file = File::create(&partial).map_err(|e| archive_error(&e))?;
+        let mut writer = ZipWriter::new(file);
+        // zipping from the temp dir keeps the archive's original layout
+        zip_dir(&mut writer, self.tempdir.path(), self.tempdir.path())
+            .map_err(|e| archive_error(&e))?;
+        writer.finish().map_err(|e| archive_error(&e))?;
+
+        fs::rename(&partial, &self.archive_path).map_err(|e| archive_error(&e))
+    }
+}
+
+// Per the UFOZ spec the archive holds a single .ufo directory, but plenty of tools zip the
+// contents directly, so look for metainfo.plist at the top level and one directory down.
+fn find_ufo_root(dir: &Path) -> Option<PathBuf> {
+    if dir.join("metainfo.plist").is_file() {
+        return Some(dir.to_owned());
+    }
+
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| path.is_dir() && path.join("metainfo.plist").is_file())
+}
+
+fn zip_dir<W: Write + io::Seek>(writer: &mut ZipWriter<W>, root: &Path, dir: &Path) -> io::Result<()> {
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+
+    for path in entries {
+        let name = path
+            .strip_prefix(root)
+            .map_err(io::Error::other)?
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        if path.is_dir() {
+            writer.add_directory(name, options)?;
+            zip_dir(writer, root, &path)?;
+        } else {
+            writer.start_file(name, options)?;
+            writer.write_all(&fs::read(&path)?)?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,46 +1,208 @@
-use core::panic;
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, HashSet},
     ffi::OsStr,
     path::{self, Path, PathBuf},
-    process::Command,
-    str,
-    sync::mpsc::{Receiver, Sender, TryRecvError},
+    sync::{
+        mpsc::{Receiver, Sender, TryRecvError},
+        Arc,
+    },
 };
 
 use crate::{
-    interpolation, ipc, parsing::{
-        glyph_entries::{parse_tsv, GlyphEntry},
-        metadata::{parse_metadata, Metadata},
-    }
+    designspace::{Designspace, Location},
+    error::LoadError,
+    gui::{
+        designspace::DesignspaceEditor, fontinfo::FontInfoEditor, instance::InstancePreview,
+        report::ReportView,
+    },
+    interpolation::{self, correspondence, sync},
+    ipc,
+    loader::{self, LoadEvent, LoadMessage, LoadProgress},
+    parsing::{
+        glyph_entries::{is_glifjson, read_glyph, GlyphEntry},
+        layer::{Layer, DEFAULT_LAYER_NAME},
+        metadata::Metadata,
+    }, provider::{MetadataProvider, NativeProvider},
+    ufoz::{self, UfozArchive},
 };
 use egui_dock::DockState;
-use glifparser::{FlattenedGlif, Glif};
-use libmfekufo::{
-    blocks::{self, Block},
-    glyphs,
-};
-use mfek_ipc::module::available;
+use glifparser::FlattenedGlif;
+use libmfekufo::blocks::Block;
 
 pub struct UFO {
     pub metadata: Metadata,
     pub path: PathBuf,
+    // the default layer
     pub glyph_entries: Vec<GlyphEntry>,
+    // glifname → index into `glyph_entries`, kept up to date by `index_glyphs`
+    glyph_index: HashMap<String, usize>,
+    // every other layer
+    pub layers: Vec<Layer>,
+    // index into `layers` of the layer shown in the grid, None for the default layer
+    pub active_layer: Option<usize>,
+    // index into `layers` of the layer drawn behind each glyph, if any
+    pub background_layer: Option<usize>,
     pub unicode_blocks: Vec<Block>,
+    // Some while glyphs are still streaming in from the loader thread
+    pub loading: Option<LoadProgress>,
+    load_id: u64,
+    // set if this master was opened from a .ufoz, in which case `path` is the unpacked copy
+    pub archive: Option<UfozArchive>,
+    // the name of the designspace source this master was opened as, if any
+    pub source_name: Option<String>,
+    // where the master sits in the designspace, by axis name
+    pub location: Location,
+    // glyphs copied in from another master that haven't been looked at since, as saved in
+    // lib.plist
+    pub needs_review: BTreeSet<String>,
+    // the layer the designspace source reads instead of the default one, as intermediate
+    // masters do; its glyphs become `glyph_entries`
+    pub source_layer: Option<String>,
+    // marked in lib.plist as only having some of the glyphs
+    pub sparse: bool,
+}
+
+impl UFO {
+    /// The glyph `glifname` of the default layer (or the source layer, for a master read from
+    /// one).
+    pub fn get(&self, glifname: &str) -> Option<&GlyphEntry> {
+        self.glyph_index.get(glifname).map(|&idx| &self.glyph_entries[idx])
+    }
+
+    pub fn get_mut(&mut self, glifname: &str) -> Option<&mut GlyphEntry> {
+        self.glyph_index
+            .get(glifname)
+            .map(|&idx| &mut self.glyph_entries[idx])
+    }
+
+    // Must be called whenever glyphs are added to, removed from or reordered in `glyph_entries`.
+    fn index_glyphs(&mut self) {
+        self.glyph_index = self
+            .glyph_entries
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| (entry.glifname.clone(), idx))
+            .collect();
+    }
+
+    pub fn broken_glyphs(&self) -> impl Iterator<Item = &GlyphEntry> {
+        self.glyph_entries.iter().filter(|entry| entry.is_broken())
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loading.is_some()
+    }
+
+    /// What to call the master in tabs and menus.
+    pub fn display_name(&self) -> &str {
+        self.source_name.as_deref().unwrap_or(&self.metadata.name)
+    }
+
+    /// The glyphs of the layer currently shown in the grid.
+    pub fn visible_entries(&self) -> &[GlyphEntry] {
+        match self.active_layer.and_then(|idx| self.layers.get(idx)) {
+            Some(layer) => &layer.glyph_entries,
+            None => &self.glyph_entries,
+        }
+    }
+
+    /// The glyph drawn behind `glifname` in its cell, from the background layer.
+    pub fn background_entry(&self, glifname: &str) -> Option<&GlyphEntry> {
+        self.background_layer
+            .and_then(|idx| self.layers.get(idx))
+            .and_then(|layer| layer.get(glifname))
+    }
+
+    pub fn layer_name(&self, layer: Option<usize>) -> &str {
+        match layer.and_then(|idx| self.layers.get(idx)) {
+            Some(layer) => &layer.name,
+            None => DEFAULT_LAYER_NAME,
+        }
+    }
+
+    fn all_entries_mut(&mut self) -> impl Iterator<Item = &mut GlyphEntry> {
+        self.glyph_entries
+            .iter_mut()
+            .chain(self.layers.iter_mut().flat_map(|l| l.glyph_entries.iter_mut()))
+    }
+
+    /// Whether the master may lack glyphs the others have, without that being a problem. Masters
+    /// read from a layer only ever hold the glyphs that change there.
+    pub fn is_sparse(&self) -> bool {
+        self.sparse || self.source_layer.is_some()
+    }
+
+    /// Makes the layer `name` this master's glyphs, for a designspace source that reads it.
+    pub fn use_layer(&mut self, name: &str) -> Result<(), LoadError> {
+        let layer = match self.layers.iter().find(|l| l.name == name) {
+            Some(layer) => layer,
+            None => {
+                return Err(LoadError::Font {
+                    path: self.path.clone(),
+                    message: format!("it has no layer named {}", name),
+                })
+            }
+        };
+
+        self.glyph_entries = layer.glyph_entries.clone();
+        self.glyph_entries
+            .sort_by(|a, b| a.codepoints.cmp(&b.codepoints));
+        self.index_glyphs();
+        self.source_layer = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Where the master lives on disk as far as the user is concerned.
+    pub fn source_path(&self) -> &Path {
+        match &self.archive {
+            Some(archive) => &archive.archive_path,
+            None => &self.path,
+        }
+    }
+}
+
+/// What a dock tab shows. Indices are into `UFOViewer::masters`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tab {
+    Master(usize),
+    FontInfo(usize),
+    Designspace,
+    // the masters interpolated live
+    Instance,
+    // what keeps the masters from interpolating
+    Report,
 }
 
 //#[derive(Default)]
 pub struct UFOViewer {
     pub active_master_idx: Option<usize>,
     pub masters: Vec<UFO>,
-    pub dockstate: DockState<usize>,
+    pub dockstate: DockState<Tab>,
+    pub fontinfo_editors: HashMap<usize, FontInfoEditor>,
     pub filter_string: String,
     pub filter_block: Option<String>,
     pub sort_by_blocks: bool,
     pub glyph_name_map: HashMap<String, usize>,
     pub interpolation_check: Option<interpolation::InterpolationCheckResults>,
+    // set if the masters were opened from a .designspace, or one is being written
+    pub designspace: Option<Designspace>,
+    pub designspace_editor: DesignspaceEditor,
+    pub instance_preview: InstancePreview,
+    pub report_view: ReportView,
+    // problems encountered while loading, waiting to be shown to the user
+    pub load_errors: Vec<LoadError>,
     should_exit: bool,
     pub dirty: bool,
+    // glyphs whose thumbnails need redrawing in every master, short of everything being `dirty`
+    pub dirty_glyphs: HashSet<String>,
+
+    // where glyph lists and fontinfo come from
+    provider: Arc<dyn MetadataProvider>,
+
+    // background loading
+    next_load_id: u64,
+    load_tx: Sender<LoadMessage>,
+    load_rx: Receiver<LoadMessage>,
 
     // filesystem watching
     pub(crate) filesystem_watch_tx: Sender<path::PathBuf>,
@@ -50,10 +212,12 @@ pub struct UFOViewer {
 impl Default for UFOViewer {
     fn default() -> Self {
         let (fstx, fsrx) = std::sync::mpsc::channel();
+        let (ldtx, ldrx) = std::sync::mpsc::channel();
 
         UFOViewer {
             active_master_idx: None,
             dockstate: DockState::new(vec![]),
+            fontinfo_editors: Default::default(),
             filesystem_watch_tx: fstx,
             filesystem_watch_rx: fsrx,
             masters: Default::default(),
@@ -63,7 +227,17 @@ impl Default for UFOViewer {
             glyph_name_map: Default::default(),
             should_exit: Default::default(),
             interpolation_check: None,
+            designspace: None,
+            designspace_editor: Default::default(),
+            instance_preview: Default::default(),
+            report_view: Default::default(),
+            load_errors: Vec::new(),
             dirty: false,
+            dirty_glyphs: HashSet::new(),
+            provider: Arc::new(NativeProvider),
+            next_load_id: 0,
+            load_tx: ldtx,
+            load_rx: ldrx,
         }
     }
 }
@@ -77,145 +251,416 @@ impl UFOViewer {
         self.active_master_idx = Some(idx);
     }
 
-    pub fn set_font(&mut self, path:&PathBuf) {
-        self.masters = Vec::new();
-        self.set_active_master(0);
+    pub fn set_provider(&mut self, provider: Box<dyn MetadataProvider>) {
+        self.provider = Arc::from(provider);
+    }
 
-        let ufo = self.load_ufo_from_path(path);
-        self.populate_glyph_name_map(&ufo);
-        self.masters.push(ufo);
-        self.dockstate.push_to_focused_leaf(self.masters.len() - 1);
+    // Fatal errors leave the currently open font untouched. Glyphs are parsed in the background
+    // afterwards; those that fail don't keep the font from opening, but are queued in
+    // `load_errors` so the user gets told about them.
+    pub fn set_font(&mut self, path:&PathBuf) -> Result<(), LoadError> {
+        let ufo = self.begin_loading_ufo(path)?;
+        self.replace_masters(vec![ufo]);
+        self.designspace = None;
+        Ok(())
+    }
+
+    /// Opens every source of a .designspace as a master, at its location. Tabs are named after
+    /// the sources.
+    pub fn open_designspace(&mut self, path: &PathBuf) -> Result<(), LoadError> {
+        let designspace = Designspace::load(path)?;
+        if designspace.sources.is_empty() {
+            return Err(LoadError::Designspace {
+                path: path.clone(),
+                message: "it has no sources".to_string(),
+            });
+        }
 
-        ipc::launch_fs_watcher(self, path);
+        let mut masters = Vec::new();
+        for source in &designspace.sources {
+            let mut ufo = self.begin_loading_ufo(&designspace.source_path(source))?;
+            ufo.source_name = source.name.clone();
+            ufo.location = designspace.full_location(&source.location);
+            // the layer's glyphs are swapped in once the layers have loaded
+            ufo.source_layer = source.layer.clone();
+            masters.push(ufo);
+        }
+
+        self.replace_masters(masters);
+        self.designspace = Some(designspace);
+        if self.masters.len() > 1 {
+            self.open_instance_preview();
+        }
+        Ok(())
     }
 
-    pub fn add_master(&mut self, path: &PathBuf) {
-        let ufo = self.load_ufo_from_path(path);
+    fn replace_masters(&mut self, masters: Vec<UFO>) {
+        self.masters = Vec::new();
+        self.dockstate = DockState::new(vec![]);
+        self.fontinfo_editors.clear();
+        self.set_active_master(0);
 
+        for ufo in masters {
+            let ufo_path = ufo.path.clone();
+            self.masters.push(ufo);
+            self.dockstate.push_to_focused_leaf(Tab::Master(self.masters.len() - 1));
+            ipc::launch_fs_watcher(self, &ufo_path);
+        }
+        self.glyph_name_map.clear();
+        self.interpolation_check = None;
+        self.report_view = Default::default();
+        self.dirty = true;
+    }
+
+    pub fn add_master(&mut self, path: &PathBuf) -> Result<(), LoadError> {
         for master in &self.masters {
-            if master.path == ufo.path {
-                return;
+            if master.source_path() == path {
+                return Ok(());
             }
         }
 
+        let ufo = self.begin_loading_ufo(path)?;
+
         self.masters.push(ufo);
-        self.dockstate.push_to_focused_leaf(self.masters.len() - 1);
+        self.dockstate.push_to_focused_leaf(Tab::Master(self.masters.len() - 1));
+        if self.masters.len() == 2 {
+            self.open_instance_preview();
+        }
         self.dirty = true;
-        self.interpolation_check = Some(interpolation::check_interpolatable(&self.masters));
+        Ok(())
+    }
+
+    /// Loads a UFO completely before returning, on the calling thread.
+    pub fn load_ufo_from_path(&mut self, path: &PathBuf) -> Result<UFO, LoadError> {
+        let (ufo_path, archive) = Self::unpack(path)?;
+        let glyph_entries = self.provider.glyph_entries(&ufo_path)?;
+        let layers = self.provider.layers(&ufo_path)?;
+        let metadata = self.provider.metadata(&ufo_path)?;
+        let unicode_blocks = loader::unicode_blocks(&ufo_path)?;
+
+        let mut ufo = UFO {
+            metadata,
+            glyph_entries,
+            glyph_index: HashMap::new(),
+            layers,
+            active_layer: None,
+            background_layer: None,
+            unicode_blocks,
+            path: ufo_path.clone(),
+            loading: None,
+            load_id: self.take_load_id(),
+            archive,
+            source_name: None,
+            location: Location::new(),
+            needs_review: sync::read_review_marks(&ufo_path),
+            source_layer: None,
+            sparse: sync::read_sparse(&ufo_path),
+        };
+        ufo.index_glyphs();
+        Ok(ufo)
+    }
+
+    // `path` may be a .ufoz, which gets unpacked. Returns the path of the .ufo directory to read.
+    fn unpack(path: &PathBuf) -> Result<(PathBuf, Option<UfozArchive>), LoadError> {
+        if ufoz::is_ufoz(path) {
+            let archive = UfozArchive::open(path)?;
+            Ok((archive.ufo_path.clone(), Some(archive)))
+        } else {
+            Ok((path.clone(), None))
+        }
+    }
+
+    // Reads fontinfo right away, so that a font we can't open at all is reported immediately, and
+    // leaves the glyphs to the loader thread. The returned UFO starts out empty. `path` may be a
+    // .ufoz, which is unpacked first.
+    fn begin_loading_ufo(&mut self, path: &PathBuf) -> Result<UFO, LoadError> {
+        let (ufo_path, archive) = Self::unpack(path)?;
+
+        let metadata = self.provider.metadata(&ufo_path)?;
+        let load_id = self.take_load_id();
+
+        loader::spawn(self.provider.clone(), ufo_path.clone(), load_id, self.load_tx.clone());
+
+        Ok(UFO {
+            metadata,
+            glyph_entries: Vec::new(),
+            glyph_index: HashMap::new(),
+            layers: Vec::new(),
+            active_layer: None,
+            background_layer: None,
+            unicode_blocks: Vec::new(),
+            path: ufo_path.clone(),
+            loading: Some(LoadProgress::default()),
+            load_id,
+            archive,
+            source_name: None,
+            location: Location::new(),
+            needs_review: sync::read_review_marks(&ufo_path),
+            source_layer: None,
+            sparse: sync::read_sparse(&ufo_path),
+        })
+    }
+
+    /// Focuses the Font Info tab of master `idx`, opening it if need be.
+    pub fn open_fontinfo(&mut self, idx: usize) {
+        let tab = Tab::FontInfo(idx);
+        match self.dockstate.find_tab(&tab) {
+            Some(location) => self.dockstate.set_active_tab(location),
+            None => self.dockstate.push_to_focused_leaf(tab),
+        }
+    }
+
+    /// Focuses the Designspace tab, opening it if need be. Starts an empty document if the
+    /// masters weren't opened from one.
+    pub fn open_designspace_editor(&mut self) {
+        self.designspace.get_or_insert_with(Designspace::default);
+
+        match self.dockstate.find_tab(&Tab::Designspace) {
+            Some(location) => self.dockstate.set_active_tab(location),
+            None => self.dockstate.push_to_focused_leaf(Tab::Designspace),
+        }
     }
 
-    pub fn load_ufo_from_path(&mut self, path: &PathBuf) -> UFO {
-        if let Ok((v, pbuf)) = available("metadata", "0.0.4") {
-            match v {
-                mfek_ipc::module::Version::OutOfDate(_) => {
-                    log::warn!("Version mismatch found with mfekmetadata!")
+    /// Focuses the Instance tab, opening it if need be.
+    pub fn open_instance_preview(&mut self) {
+        match self.dockstate.find_tab(&Tab::Instance) {
+            Some(location) => self.dockstate.set_active_tab(location),
+            None => self.dockstate.push_to_focused_leaf(Tab::Instance),
+        }
+    }
+
+    /// Focuses the Interpolation Report tab, opening it if need be.
+    pub fn open_report(&mut self) {
+        match self.dockstate.find_tab(&Tab::Report) {
+            Some(location) => self.dockstate.set_active_tab(location),
+            None => self.dockstate.push_to_focused_leaf(Tab::Report),
+        }
+    }
+
+    /// Rewrites glyph `glifname` in each of the `targets` masters so that its contours line up
+    /// with the `reference` master's, then checks the masters again. Returns how many masters
+    /// were changed.
+    pub fn match_contours(
+        &mut self,
+        glifname: &str,
+        reference: usize,
+        targets: &[usize],
+    ) -> Result<usize, LoadError> {
+        let reference_glif = match self.masters[reference].get(glifname) {
+            Some(entry) => entry.glif.clone(),
+            None => return Ok(0),
+        };
+
+        let mut changed = 0;
+        let mut result = Ok(());
+        for &target in targets {
+            let entry = match self.masters[target].get_mut(glifname) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            match correspondence::rewrite(&reference_glif, entry) {
+                Ok(Some(glif)) => {
+                    entry.glif = glif;
+                    changed += 1;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    result = Err(e);
+                    break;
                 }
-                _ => {}
             }
+        }
 
-            let glyph_entries = self.fetch_glyph_entries(&pbuf, &path);
-            let metadata = self.fetch_metadata(&pbuf, &path);
-            let unicode_blocks = Self::get_unicode_blocks(path.clone());
+        if changed > 0 {
+            self.recheck_glyph(glifname);
+        }
+        result.map(|_| changed)
+    }
 
-            UFO {
-                metadata,
-                glyph_entries,
-                unicode_blocks,
-                path: path.clone()
+    /// Copies every glyph of master `reference` that other masters lack into them, optionally
+    /// scaled to their vertical metrics, and marks the copies for review. Sparse masters are left
+    /// alone. Returns how many glyphs were copied.
+    pub fn synchronize_glyph_sets(
+        &mut self,
+        reference: usize,
+        scale: bool,
+    ) -> Result<usize, LoadError> {
+        let mut copies: Vec<(usize, GlyphEntry)> = Vec::new();
+        let mut result = Ok(());
+
+        'masters: for (mi, target) in self.masters.iter().enumerate() {
+            if mi == reference || target.is_sparse() {
+                continue;
+            }
+            for entry in &self.masters[reference].glyph_entries {
+                if target.get(&entry.glifname).is_some() {
+                    continue;
+                }
+                match sync::copy_glyph(entry, &self.masters[reference], target, scale) {
+                    Ok(copy) => copies.push((mi, copy)),
+                    Err(e) => {
+                        result = Err(e);
+                        break 'masters;
+                    }
+                }
             }
-        } else {
-            panic!("Failed to locate mfekmetadata! Is it installed on your system?")
         }
-    }
 
-    fn populate_glyph_name_map(&mut self, ufo: &UFO) {
-        self.glyph_name_map.clear();
+        // whatever made it to disk is kept, even if a later copy failed
+        let copied = copies.len();
+        let mut changed_masters = BTreeSet::new();
+        let mut changed_glyphs = BTreeSet::new();
+        for (mi, copy) in copies {
+            let master = &mut self.masters[mi];
+            master.needs_review.insert(copy.glifname.clone());
+            changed_glyphs.insert(copy.glifname.clone());
+            master.glyph_entries.push(copy);
+            changed_masters.insert(mi);
+        }
+        for mi in changed_masters {
+            let master = &mut self.masters[mi];
+            master.glyph_entries.sort_by(|a, b| a.codepoints.cmp(&b.codepoints));
+            master.index_glyphs();
+            if let Err(e) = sync::write_review_marks(&master.path, &master.needs_review) {
+                result = result.and(Err(e));
+            }
+        }
 
-        for (idx, entry) in ufo.glyph_entries.iter().enumerate() {
-            self.glyph_name_map.insert(entry.glifname.clone(), idx);
+        for glifname in &changed_glyphs {
+            self.recheck_glyph(glifname);
         }
+        self.populate_glyph_name_map();
+        result.map(|_| copied)
     }
 
-    fn get_unicode_blocks<P: AsRef<Path>>(path: P) -> Vec<Block> {
-        let gvec = glyphs::for_ufo(path.as_ref().to_str().unwrap().to_owned());
-        let unique_encodings = glyphs::to_unique_codepoints(&gvec);
-        let blocks = blocks::for_unicode_data(&unique_encodings);
-        blocks::grouped_by(&gvec, &blocks)
+    /// Takes `glifname` off master `idx`'s list of glyphs to review.
+    pub fn mark_reviewed(&mut self, idx: usize, glifname: &str) -> Result<(), LoadError> {
+        let master = &mut self.masters[idx];
+        if master.needs_review.remove(glifname) {
+            sync::write_review_marks(&master.path, &master.needs_review)?;
+        }
+        Ok(())
     }
 
-    fn fetch_glyph_entries<P: AsRef<Path>>(
-        &mut self,
-        metadata_path: P,
-        font_path: P,
-    ) -> Vec<GlyphEntry> {
-        let output = Command::new(metadata_path.as_ref())
-            .args([font_path.as_ref().to_str().unwrap(), "glyphs"])
-            .output()
-            .expect("Call to list glyphs from mfekmetadata failed!");
-
-        if !output.status.success() {
-            panic!(
-                "mfekmetadata returned a non-zero exit code: {0}",
-                output.status.code().unwrap()
-            )
+    /// Marks master `idx` as sparse or not in its lib.plist, then checks the masters again, as
+    /// every glyph it lacks changes from missing to fine or the other way round.
+    pub fn set_sparse(&mut self, idx: usize, sparse: bool) -> Result<(), LoadError> {
+        let master = &mut self.masters[idx];
+        if master.sparse == sparse {
+            return Ok(());
         }
+        sync::write_sparse(&master.path, sparse)?;
+        master.sparse = sparse;
 
-        // Convert the stdout Vec<u8> to a &str
-        let stdout_str =
-            str::from_utf8(&output.stdout).expect("The command's output was not valid UTF-8");
-
-        // Pass the &str to the parse_tsv function
-        match parse_tsv(stdout_str) {
-            Ok(data) => {
-                let mut data = data.clone();
-                data.sort_by(|a, b| {
-                    a.codepoints.cmp(&b.codepoints)
-                });
-                return data;
+        if self.masters.len() > 1 && !self.is_loading() {
+            self.interpolation_check = Some(interpolation::check_interpolatable(&self.masters));
+            self.dirty = true;
+        }
+        Ok(())
+    }
+
+    pub fn has_archives(&self) -> bool {
+        self.masters.iter().any(|m| m.archive.is_some())
+    }
+
+    /// Zips every master that came from a .ufoz back up. Masters opened as directories are
+    /// written to directly by MFEKglif and need no saving.
+    pub fn save_archives(&mut self) {
+        for master in &self.masters {
+            if let Some(archive) = &master.archive {
+                if let Err(e) = archive.save() {
+                    log::error!("{}", e);
+                    self.load_errors.push(e);
+                }
             }
-            Err(err) => panic!("Error parsing TSV data: {}", err),
         }
     }
 
-    fn fetch_metadata<P: AsRef<Path>>(&mut self, metadata_path: P, font_path: P) -> Metadata {
-        let metadata_path_str = font_path
-            .as_ref()
-            .to_str()
-            .expect("Failed to convert font path to str.");
-        // let's get the familyName of the ufo and store that in the viewer
-        let output = Command::new(metadata_path.as_ref())
-            .args([
-                metadata_path_str,
-                "arbitrary",
-                "-k",
-                "postscriptFullName",
-                "-k",
-                "ascender",
-                "-k",
-                "descender",
-                "-k",
-                "copyright",
-            ])
-            .output()
-            .expect("Failed to run mfekmetadata command to find familyName!");
-
-        if !output.status.success() {
-            panic!(
-                "mfekmetadata exited with a non-zero exit code: {:?} \n {:?}",
-                output.status.code().unwrap(),
-                output
-            )
+    fn take_load_id(&mut self) -> u64 {
+        self.next_load_id += 1;
+        self.next_load_id
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.masters.iter().any(UFO::is_loading)
+    }
+
+    pub fn handle_load_events(&mut self) {
+        let mut finished = false;
+
+        loop {
+            let message = match self.load_rx.try_recv() {
+                Ok(message) => message,
+                Err(TryRecvError::Empty) => break,
+                Err(_) => panic!("Loader channel disconnected!"),
+            };
+
+            // messages for a master that has since been closed are dropped
+            let ufo = match self.masters.iter_mut().find(|m| m.load_id == message.load_id) {
+                Some(ufo) => ufo,
+                None => continue,
+            };
+
+            match message.event {
+                LoadEvent::Glyphs { batch, total } => {
+                    self.load_errors
+                        .extend(batch.iter().filter_map(|entry| entry.error.clone()));
+
+                    let progress = ufo.loading.get_or_insert_with(LoadProgress::default);
+                    progress.loaded += batch.len();
+                    progress.total = total;
+
+                    merge_batch(&mut ufo.glyph_entries, batch);
+                    ufo.index_glyphs();
+                }
+                LoadEvent::Layers(layers) => {
+                    for layer in &layers {
+                        self.load_errors
+                            .extend(layer.glyph_entries.iter().filter_map(|e| e.error.clone()));
+                    }
+                    ufo.layers = layers;
+
+                    if let Some(name) = ufo.source_layer.clone() {
+                        if let Err(e) = ufo.use_layer(&name) {
+                            // better no glyphs than the default layer's passing for the layer's
+                            ufo.glyph_entries.clear();
+                            ufo.index_glyphs();
+                            self.load_errors.push(e);
+                        }
+                    }
+                }
+                LoadEvent::UnicodeBlocks(blocks) => {
+                    ufo.unicode_blocks = blocks;
+                }
+                LoadEvent::Failed(e) => {
+                    ufo.loading = None;
+                    self.load_errors.push(e);
+                    finished = true;
+                }
+                LoadEvent::Finished => {
+                    ufo.loading = None;
+                    finished = true;
+                }
+            }
         }
 
-        let stdout_str =
-            str::from_utf8(&output.stdout).expect("The command's output was not valid UTF-8");
+        if finished {
+            self.populate_glyph_name_map();
 
-        match parse_metadata(stdout_str) {
-            Ok(data) => {
-                return data;
+            // only check once every master is in, partial glyph sets would all look incompatible
+            if self.masters.len() > 1 && !self.is_loading() {
+                self.interpolation_check = Some(interpolation::check_interpolatable(&self.masters));
+                self.dirty = true;
             }
-            Err(err) => {
-                panic!("Error parsing metadata: {}", err);
+        }
+    }
+
+    fn populate_glyph_name_map(&mut self) {
+        self.glyph_name_map.clear();
+
+        if let Some(ufo) = self.masters.first() {
+            for (idx, entry) in ufo.glyph_entries.iter().enumerate() {
+                self.glyph_name_map.insert(entry.glifname.clone(), idx);
             }
         }
     }
@@ -225,24 +670,36 @@ impl UFOViewer {
             let event = self.filesystem_watch_rx.try_recv();
             match event {
                 Ok(p) => {
-                    if p.extension() == Some(OsStr::new("glif")) {
-                        // load the glif
-                        let mut glif: Glif<()> =
-                            glifparser::read_from_filename(&p).expect("Failed to load glyph!");
-                        if glif.components.vec.len() > 0 {
-                            glif = glif.flattened(&mut None).unwrap_or(glif);
-                        }
+                    if p.extension() == Some(OsStr::new("glif")) || is_glifjson(&p) {
+                        // load the glyph
+                        let (glif, layered) = match read_glyph(&p) {
+                            Ok(glyph) => glyph,
+                            Err(e) => {
+                                let error = LoadError::Glif {
+                                    path: p.clone(),
+                                    message: e,
+                                };
+                                log::error!("{}", error);
+                                self.flag_broken_glyph(&p, error);
+                                continue;
+                            }
+                        };
 
+                        let mut changed = HashSet::new();
                         for ufo in &mut self.masters {
-                            for potential_match in &mut ufo.glyph_entries {
+                            for potential_match in ufo.all_entries_mut() {
                                 if glif.filename == potential_match.glif.filename {
                                     potential_match.glif = glif.clone();
+                                    potential_match.layered = layered.clone();
+                                    potential_match.error = None;
+                                    changed.insert(potential_match.glifname.clone());
                                 }
                             }
                         }
 
-                        self.interpolation_check = Some(interpolation::check_interpolatable(&self.masters));
-                        self.dirty = true;
+                        for glifname in changed {
+                            self.recheck_glyph(&glifname);
+                        }
                     } else {
                         log::debug!("Ignored write of file {:?}", p)
                     }
@@ -253,6 +710,33 @@ impl UFOViewer {
         }
     }
 
+    // Only the changed glyph is checked again and redrawn, so that saving one glyph stays quick
+    // however many glyphs and masters there are.
+    fn recheck_glyph(&mut self, glifname: &str) {
+        match &mut self.interpolation_check {
+            Some(check) => check.recheck_glyph(&self.masters, glifname),
+            None => {
+                self.interpolation_check = Some(interpolation::check_interpolatable(&self.masters));
+                self.dirty = true;
+            }
+        }
+        self.dirty_glyphs.insert(glifname.to_string());
+    }
+
+    // A glif that was fine when the font was opened may be saved in a broken state later on.
+    fn flag_broken_glyph(&mut self, path: &Path, error: LoadError) {
+        for ufo in &mut self.masters {
+            for entry in ufo.all_entries_mut() {
+                if entry.glif.filename.as_deref() == Some(path) {
+                    entry.error = Some(error.clone());
+                }
+            }
+        }
+
+        self.load_errors.push(error);
+        self.dirty = true;
+    }
+
     pub fn exit(&mut self) {
         self.should_exit = true;
     }
@@ -261,3 +745,24 @@ impl UFOViewer {
         self.should_exit
     }
 }
+
+// Sorts `batch` by codepoint and merges it into the already sorted `entries`, so that a font
+// arriving in many batches isn't re-sorted as a whole each time.
+fn merge_batch(entries: &mut Vec<GlyphEntry>, mut batch: Vec<GlyphEntry>) {
+    batch.sort_by(|a, b| a.codepoints.cmp(&b.codepoints));
+
+    let old = std::mem::take(entries);
+    entries.reserve(old.len() + batch.len());
+    let mut old = old.into_iter().peekable();
+    let mut batch = batch.into_iter().peekable();
+    loop {
+        let take_old = match (old.peek(), batch.peek()) {
+            (Some(a), Some(b)) => a.codepoints <= b.codepoints,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => break,
+        };
+        let next = if take_old { old.next() } else { batch.next() };
+        entries.extend(next);
+    }
+}